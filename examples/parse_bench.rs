@@ -0,0 +1,70 @@
+// Times `JSON::from_str` on the large test fixtures and counts allocations.
+// Run with `cargo run --release --example parse_bench [files...]`.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use json::json::JSON;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const RUNS: u32 = 5;
+
+fn main() {
+    let mut paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        paths = vec![
+            "src/tests/users_100k.json".to_string(),
+            "src/tests/large-complex.json".to_string(),
+        ];
+    }
+
+    for path in paths {
+        let file = std::fs::read_to_string(&path).expect("Fixture should exist");
+        let base = CURRENT.load(Ordering::Relaxed);
+
+        let mut best = None;
+        for _ in 0..RUNS {
+            ALLOCATIONS.store(0, Ordering::Relaxed);
+            PEAK.store(base, Ordering::Relaxed);
+            let start = Instant::now();
+            let json = JSON::from_str(&file).expect("JSON should be valid");
+            let elapsed = start.elapsed();
+            drop(json);
+            best = Some(best.map_or(elapsed, |b: std::time::Duration| b.min(elapsed)));
+        }
+
+        println!(
+            "{}: best of {} {:.2?}, {} allocations, peak {:.1} MiB above input",
+            path,
+            RUNS,
+            best.expect("At least one run"),
+            ALLOCATIONS.load(Ordering::Relaxed),
+            (PEAK.load(Ordering::Relaxed) - base) as f64 / (1024.0 * 1024.0)
+        );
+    }
+}
@@ -1,18 +1,35 @@
+mod canonical;
+mod diff;
+mod escape;
 pub mod from_into;
+pub mod map;
+mod merge;
+pub mod number;
+pub mod patch;
+pub mod path;
+pub mod pointer;
+pub mod pretty;
+pub mod writer;
 
-use std::{
-    collections::HashMap,
-    fmt::{Display, Formatter},
-};
+pub use diff::{Change, DiffConfig};
+pub use map::Map;
+pub use number::Number;
+pub use patch::{Patch, PatchError};
+pub use path::{JSONPath, PathError, PathMatch};
+pub use pointer::PointerError;
+pub use pretty::{Indent, Newline, NonFinite, PrettyConfig};
+pub use writer::{JSONWriter, JSONWriterError, NDJSONWriter};
+
+use std::fmt::{Display, Formatter};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum JSON {
     Null,
     Bool(bool),
-    Number(f64),
+    Number(Number),
     String(String),
     Array(Vec<JSON>),
-    Object(HashMap<String, JSON>),
+    Object(Map),
 }
 
 pub enum JSONMethodError {
@@ -27,13 +44,25 @@ impl JSON {
         }
     }
 
-    pub fn as_number(&self) -> Option<f64> {
+    pub fn as_number(&self) -> Option<&Number> {
         match self {
-            JSON::Number(n) => Some(*n),
+            JSON::Number(n) => Some(n),
             _ => None,
         }
     }
 
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().and_then(Number::as_f64)
+    }
+
     pub fn as_string(&self) -> Option<&str> {
         match self {
             JSON::String(s) => Some(s),
@@ -48,7 +77,7 @@ impl JSON {
         }
     }
 
-    pub fn as_object(&self) -> Option<&HashMap<String, JSON>> {
+    pub fn as_object(&self) -> Option<&Map> {
         match self {
             JSON::Object(o) => Some(o),
             _ => None,
@@ -104,33 +133,45 @@ impl JSON {
     }
 }
 
+impl JSON {
+    // Same as `to_string`, but every non-ASCII character is written as `\uXXXX`
+    pub fn to_ascii_string(&self) -> String {
+        self.write_string(&PrettyConfig {
+            ascii_only: true,
+            ..PrettyConfig::inline()
+        })
+    }
+
+    // Indented JSON5 with unquoted identifier keys, readable by `JSON::from_json5`
+    pub fn to_json5_string(&self) -> String {
+        self.write_string(&PrettyConfig {
+            json5: true,
+            ..PrettyConfig::default()
+        })
+    }
+
+    // Fails with `NonFiniteNumber` if `config.non_finite` is `NonFinite::Error`
+    // and the value holds NaN or an infinity
+    pub fn to_string_pretty(&self, config: &PrettyConfig) -> Result<String, JSONWriterError> {
+        config.check_finite(self)?;
+        Ok(self.write_string(config))
+    }
+
+    // For a `config` whose `non_finite` is not `NonFinite::Error`, which can't fail
+    pub(crate) fn write_string(&self, config: &PrettyConfig) -> String {
+        let mut out = String::new();
+        pretty::write_value(&mut out, self, config, 0).expect("Writing to a String cannot fail");
+        out
+    }
+}
+
+// `{}` writes a single line, `{:#}` indents with two spaces
 impl Display for JSON {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        match self {
-            JSON::Null => write!(f, "null"),
-            JSON::Bool(b) => write!(f, "{}", b),
-            JSON::Number(n) => write!(f, "{}", n),
-            JSON::String(s) => write!(f, "\"{}\"", s),
-            JSON::Array(a) => {
-                write!(f, "[")?;
-                for (i, v) in a.iter().enumerate() {
-                    if i != 0 {
-                        write!(f, ", ")?;
-                    }
-                    write!(f, "{}", v)?;
-                }
-                write!(f, "]")
-            }
-            JSON::Object(o) => {
-                write!(f, "{{")?;
-                for (i, (k, v)) in o.iter().enumerate() {
-                    if i != 0 {
-                        write!(f, ", ")?;
-                    }
-                    write!(f, "\"{}\": {}", k, v)?;
-                }
-                write!(f, "}}")
-            }
+        if f.alternate() {
+            pretty::write_value(f, self, &PrettyConfig::default(), 0)
+        } else {
+            pretty::write_value(f, self, &PrettyConfig::inline(), 0)
         }
     }
 }
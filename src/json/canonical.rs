@@ -0,0 +1,69 @@
+use std::{
+    fmt::{self, Write},
+    io,
+};
+
+use super::{
+    escape, number, pretty::check_finite, writer::IoAdapter, JSONWriterError, Number, JSON,
+};
+
+impl JSON {
+    // RFC 8785 JSON Canonicalization Scheme: no whitespace, object keys sorted
+    // by UTF-16 code units, numbers written as ECMAScript would and strings with
+    // only the escapes JSON requires. NaN and infinities can't be represented.
+    pub fn to_canonical_string(&self) -> Result<String, JSONWriterError> {
+        check_finite(self, true)?;
+        let mut out = String::new();
+        write_canonical(&mut out, self).expect("Writing to a String cannot fail");
+        Ok(out)
+    }
+
+    pub fn write_canonical_to<W: io::Write>(&self, w: W) -> Result<(), JSONWriterError> {
+        check_finite(self, true)?;
+        let mut adapter = IoAdapter::new(w);
+        let result = write_canonical(&mut adapter, self);
+        Ok(adapter.take_error(result)?)
+    }
+}
+
+fn write_canonical<W: Write + ?Sized>(w: &mut W, value: &JSON) -> fmt::Result {
+    match value {
+        JSON::Null => w.write_str("null"),
+        JSON::Bool(b) => write!(w, "{}", b),
+        JSON::Number(n) => write_number(w, n),
+        JSON::String(s) => escape::write_escaped(w, s, false),
+        JSON::Array(a) => {
+            w.write_char('[')?;
+            for (i, v) in a.iter().enumerate() {
+                if i != 0 {
+                    w.write_char(',')?;
+                }
+                write_canonical(w, v)?;
+            }
+            w.write_char(']')
+        }
+        JSON::Object(o) => {
+            let mut members: Vec<(&String, &JSON)> = o.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            w.write_char('{')?;
+            for (i, (k, v)) in members.into_iter().enumerate() {
+                if i != 0 {
+                    w.write_char(',')?;
+                }
+                escape::write_escaped(w, k, false)?;
+                w.write_char(':')?;
+                write_canonical(w, v)?;
+            }
+            w.write_char('}')
+        }
+    }
+}
+
+// Every number is treated as an IEEE 754 double, so large integers lose
+// precision, and negative zero is written as `0`
+fn write_number<W: Write + ?Sized>(w: &mut W, n: &Number) -> fmt::Result {
+    match n.as_f64().expect("Checked by check_finite") {
+        0.0 => w.write_char('0'),
+        f => number::write_f64(w, f),
+    }
+}
@@ -0,0 +1,357 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use super::{pointer::push_token, Number, PrettyConfig, JSON};
+
+// Controls how `JSON::diff_with` compares values. With `arrays_as_sets` the
+// order of array elements is ignored; numbers within `float_tolerance` of each
+// other are considered equal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffConfig {
+    pub arrays_as_sets: bool,
+    pub float_tolerance: f64,
+}
+
+impl DiffConfig {
+    pub fn new() -> DiffConfig {
+        DiffConfig::default()
+    }
+}
+
+// A single difference, located by a JSON pointer. Removed array elements are
+// located by their index in the old value, added ones by their index in the new.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<'a> {
+    Added {
+        path: String,
+        new: &'a JSON,
+    },
+    Removed {
+        path: String,
+        old: &'a JSON,
+    },
+    Changed {
+        path: String,
+        old: &'a JSON,
+        new: &'a JSON,
+    },
+}
+
+impl<'a> Change<'a> {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl<'a> Display for Change<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Change::Added { path, new } => write!(f, "added {}: {}", path, new),
+            Change::Removed { path, old } => write!(f, "removed {}: {}", path, old),
+            Change::Changed { path, old, new } => {
+                write!(f, "changed {}: {} -> {}", path, old, new)
+            }
+        }
+    }
+}
+
+impl JSON {
+    // Lists the differences between `self` and `other`, comparing arrays in order
+    // and numbers exactly. Members only in `self` are `Removed`.
+    pub fn diff<'a>(&'a self, other: &'a JSON) -> Vec<Change<'a>> {
+        self.diff_with(other, &DiffConfig::default())
+    }
+
+    pub fn diff_with<'a>(&'a self, other: &'a JSON, config: &DiffConfig) -> Vec<Change<'a>> {
+        let mut changes = Vec::new();
+        diff_values(&mut changes, &mut String::new(), self, other, config);
+        changes
+    }
+
+    // Renders both values pretty printed with sorted keys and diffs them line
+    // by line, like `diff -u`. Returns an empty string if the text is the same.
+    // With `color` removed lines are red, added lines green and hunk headers cyan.
+    pub fn unified_diff(&self, other: &JSON, color: bool) -> String {
+        let config = PrettyConfig {
+            sort_keys: true,
+            ..PrettyConfig::default()
+        };
+        let old = self.write_string(&config);
+        let new = other.write_string(&config);
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+        let edits = diff_lines(&old, &new);
+        if edits.iter().all(|e| matches!(e, Edit::Equal(..))) {
+            return String::new();
+        }
+
+        let paint = |code: &'static str| if color { code } else { "" };
+        let (red, green, cyan, reset) = (
+            paint("\x1b[31m"),
+            paint("\x1b[32m"),
+            paint("\x1b[36m"),
+            paint("\x1b[0m"),
+        );
+        let mut out = format!("{}--- old{}\n{}+++ new{}\n", red, reset, green, reset);
+        for hunk in hunks(&edits) {
+            let edits = &edits[hunk];
+            let (old_start, new_start) = match edits[0] {
+                Edit::Equal(o, n) | Edit::Delete(o, n) | Edit::Insert(o, n) => (o, n),
+            };
+            let old_len = edits
+                .iter()
+                .filter(|e| !matches!(e, Edit::Insert(..)))
+                .count();
+            let new_len = edits
+                .iter()
+                .filter(|e| !matches!(e, Edit::Delete(..)))
+                .count();
+            // Like `diff -u`, an empty range names the line before it
+            let start = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+            writeln!(
+                out,
+                "{}@@ -{},{} +{},{} @@{}",
+                cyan,
+                start(old_start, old_len),
+                old_len,
+                start(new_start, new_len),
+                new_len,
+                reset
+            )
+            .expect("Writing to a String");
+            for edit in edits {
+                match *edit {
+                    Edit::Equal(o, _) => writeln!(out, " {}", old[o]),
+                    Edit::Delete(o, _) => writeln!(out, "{}-{}{}", red, old[o], reset),
+                    Edit::Insert(_, n) => writeln!(out, "{}+{}{}", green, new[n], reset),
+                }
+                .expect("Writing to a String");
+            }
+        }
+        out
+    }
+}
+
+fn diff_values<'a>(
+    changes: &mut Vec<Change<'a>>,
+    path: &mut String,
+    old: &'a JSON,
+    new: &'a JSON,
+    config: &DiffConfig,
+) {
+    let len = path.len();
+    match (old, new) {
+        (JSON::Object(a), JSON::Object(b)) => {
+            for (key, old) in a {
+                push_token(path, key);
+                match b.get(key) {
+                    Some(new) => diff_values(changes, path, old, new, config),
+                    None => changes.push(Change::Removed {
+                        path: path.clone(),
+                        old,
+                    }),
+                }
+                path.truncate(len);
+            }
+            for (key, new) in b.iter().filter(|(key, _)| !a.contains_key(key)) {
+                push_token(path, key);
+                changes.push(Change::Added {
+                    path: path.clone(),
+                    new,
+                });
+                path.truncate(len);
+            }
+        }
+        (JSON::Array(a), JSON::Array(b)) if config.arrays_as_sets => {
+            let (removed, added) = unmatched(a, b, config);
+            for i in removed {
+                push_token(path, &i.to_string());
+                changes.push(Change::Removed {
+                    path: path.clone(),
+                    old: &a[i],
+                });
+                path.truncate(len);
+            }
+            for i in added {
+                push_token(path, &i.to_string());
+                changes.push(Change::Added {
+                    path: path.clone(),
+                    new: &b[i],
+                });
+                path.truncate(len);
+            }
+        }
+        (JSON::Array(a), JSON::Array(b)) => {
+            for (i, (old, new)) in a.iter().zip(b).enumerate() {
+                push_token(path, &i.to_string());
+                diff_values(changes, path, old, new, config);
+                path.truncate(len);
+            }
+            for (i, old) in a.iter().enumerate().skip(b.len()) {
+                push_token(path, &i.to_string());
+                changes.push(Change::Removed {
+                    path: path.clone(),
+                    old,
+                });
+                path.truncate(len);
+            }
+            for (i, new) in b.iter().enumerate().skip(a.len()) {
+                push_token(path, &i.to_string());
+                changes.push(Change::Added {
+                    path: path.clone(),
+                    new,
+                });
+                path.truncate(len);
+            }
+        }
+        _ if equal(old, new, config) => (),
+        _ => changes.push(Change::Changed {
+            path: path.clone(),
+            old,
+            new,
+        }),
+    }
+}
+
+fn equal(a: &JSON, b: &JSON, config: &DiffConfig) -> bool {
+    match (a, b) {
+        (JSON::Number(x), JSON::Number(y)) => numbers_equal(x, y, config.float_tolerance),
+        (JSON::Array(x), JSON::Array(y)) if config.arrays_as_sets => {
+            let (removed, added) = unmatched(x, y, config);
+            removed.is_empty() && added.is_empty()
+        }
+        (JSON::Array(x), JSON::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| equal(x, y, config))
+        }
+        (JSON::Object(x), JSON::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|w| equal(v, w, config)))
+        }
+        _ => a == b,
+    }
+}
+
+fn numbers_equal(x: &Number, y: &Number, tolerance: f64) -> bool {
+    if x == y {
+        return true;
+    }
+    match (x.as_f64(), y.as_f64()) {
+        (Some(x), Some(y)) if tolerance > 0.0 => (x - y).abs() <= tolerance,
+        _ => false,
+    }
+}
+
+// Pairs each element of `a` with the first equal, not yet paired element of `b`
+// and returns the indices left over on each side
+fn unmatched(a: &[JSON], b: &[JSON], config: &DiffConfig) -> (Vec<usize>, Vec<usize>) {
+    let mut paired = vec![false; b.len()];
+    let mut removed = Vec::new();
+    for (i, x) in a.iter().enumerate() {
+        let found = (0..b.len()).find(|&j| !paired[j] && equal(x, &b[j], config));
+        match found {
+            Some(j) => paired[j] = true,
+            None => removed.push(i),
+        }
+    }
+    let added = (0..b.len()).filter(|&j| !paired[j]).collect();
+    (removed, added)
+}
+
+// Line indices in the old and new text. `Insert` carries the old line it comes
+// before and `Delete` the new line it comes before, so every edit knows where
+// it sits on both sides.
+#[derive(Clone, Copy, Debug)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize, usize),
+    Insert(usize, usize),
+}
+
+// Myers' O((N + M) D) shortest edit script. After each round only the
+// diagonals it can have reached are kept for the backtrack, so memory is O(D^2)
+// on top of the input.
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let offset = n + m + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // `trace[d][k + d]` is how far along diagonal `k` round `d` got
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=n + m {
+        for k in (-d..=d).step_by(2) {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    // Round `d` is undone using where round `d - 1` got to
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize + 1;
+        let reached = |k: isize| v[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && reached(k - 1) < reached(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = reached(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal(x as usize, y as usize));
+        }
+        if x == prev_x {
+            edits.push(Edit::Insert(x as usize, prev_y as usize));
+        } else {
+            edits.push(Edit::Delete(prev_x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    // Round 0 only follows the diagonal from the start
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        edits.push(Edit::Equal(x as usize, y as usize));
+    }
+    edits.reverse();
+    edits
+}
+
+// Groups changed lines with up to three lines of context around them
+fn hunks(edits: &[Edit]) -> Vec<std::ops::Range<usize>> {
+    const CONTEXT: usize = 3;
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Equal(..)) {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if last.end >= start => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
@@ -0,0 +1,48 @@
+use std::fmt::{Result, Write};
+
+// Writes `s` as a quoted JSON string. With `ascii_only` every non-ASCII
+// character is written as a `\uXXXX` escape (surrogate pairs above U+FFFF).
+pub(crate) fn write_escaped<W: Write + ?Sized>(w: &mut W, s: &str, ascii_only: bool) -> Result {
+    write_quoted(w, s, '"', ascii_only)
+}
+
+// Like `write_escaped`, but delimited by `quote`, which JSON5 lets be `'`
+pub(crate) fn write_quoted<W: Write + ?Sized>(
+    w: &mut W,
+    s: &str,
+    quote: char,
+    ascii_only: bool,
+) -> Result {
+    w.write_char(quote)?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let needs_escape = c == quote || c == '\\' || c < '\x20' || (ascii_only && !c.is_ascii());
+        if !needs_escape {
+            continue;
+        }
+        if start < i {
+            w.write_str(&s[start..i])?;
+        }
+        start = i + c.len_utf8();
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\'' => w.write_str("\\'")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            '\x08' => w.write_str("\\b")?,
+            '\x0c' => w.write_str("\\f")?,
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(w, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+    }
+    if start < s.len() {
+        w.write_str(&s[start..])?;
+    }
+    w.write_char(quote)
+}
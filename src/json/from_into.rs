@@ -1,13 +1,25 @@
 use std::collections::HashMap;
 
-use super::JSON;
+use super::{Map, Number, JSON};
 
-impl From<f64> for JSON {
-    fn from(value: f64) -> Self {
+impl From<Number> for JSON {
+    fn from(value: Number) -> Self {
         JSON::Number(value)
     }
 }
 
+macro_rules! from_number {
+    ($($t:ty),*) => {$(
+        impl From<$t> for JSON {
+            fn from(value: $t) -> Self {
+                JSON::Number(Number::from(value))
+            }
+        }
+    )*};
+}
+
+from_number!(f64, f32, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
 impl From<String> for JSON {
     fn from(value: String) -> Self {
         JSON::String(value)
@@ -38,7 +50,7 @@ where
     V: Into<JSON>,
 {
     fn from(value: HashMap<U, V>) -> Self {
-        let mut map = HashMap::new();
+        let mut map = Map::with_capacity(value.len());
         for (key, val) in value {
             map.insert(key.into(), val.into());
         }
@@ -46,6 +58,12 @@ where
     }
 }
 
+impl From<Map> for JSON {
+    fn from(value: Map) -> Self {
+        JSON::Object(value)
+    }
+}
+
 impl<U> From<Vec<U>> for JSON
 where
     U: Into<JSON>,
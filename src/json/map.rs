@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    ops::{Index, IndexMut},
+};
+
+use super::JSON;
+
+// Objects with more members than this get a hash index; smaller ones are scanned
+const INDEX_THRESHOLD: usize = 16;
+
+// The members of a JSON object, kept in insertion order
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, JSON)>,
+    index: Option<HashMap<String, usize>>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Map {
+        Map {
+            entries: Vec::with_capacity(capacity),
+            index: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(key).copied(),
+            None => self.entries.iter().position(|(k, _)| k == key),
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JSON> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JSON> {
+        self.position(key).map(|i| &mut self.entries[i].1)
+    }
+
+    // Replacing an existing key keeps its original position
+    pub fn insert(&mut self, key: String, value: JSON) -> Option<JSON> {
+        if let Some(i) = self.position(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        if let Some(index) = &mut self.index {
+            index.insert(key.clone(), self.entries.len());
+        }
+        self.entries.push((key, value));
+        if self.index.is_none() && self.entries.len() > INDEX_THRESHOLD {
+            self.rebuild_index();
+        }
+        None
+    }
+
+    // Removes `key`, shifting later members down to keep the order
+    pub fn remove(&mut self, key: &str) -> Option<JSON> {
+        let i = self.position(key)?;
+        let (key, value) = self.entries.remove(i);
+        // Only the members after `i` moved, so their slots are fixed up in place
+        if let Some(index) = &mut self.index {
+            index.remove(&key);
+            for (k, _) in &self.entries[i..] {
+                *index.get_mut(k).expect("Indexed key") -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index = None;
+    }
+
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if self.index.is_some() {
+            self.rebuild_index();
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index = Some(
+            self.entries
+                .iter()
+                .enumerate()
+                .map(|(i, (k, _))| (k.clone(), i))
+                .collect(),
+        );
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JSON> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JSON> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+}
+
+// Two objects are equal if they have the same members, in any order
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl Debug for Map {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Index<&str> for Map {
+    type Output = JSON;
+
+    fn index(&self, key: &str) -> &JSON {
+        self.get(key).expect("Key not present in object")
+    }
+}
+
+impl IndexMut<&str> for Map {
+    fn index_mut(&mut self, key: &str) -> &mut JSON {
+        self.get_mut(key).expect("Key not present in object")
+    }
+}
+
+impl FromIterator<(String, JSON)> for Map {
+    fn from_iter<T: IntoIterator<Item = (String, JSON)>>(iter: T) -> Map {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(String, JSON)> for Map {
+    fn extend<T: IntoIterator<Item = (String, JSON)>>(&mut self, iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+pub struct Iter<'a>(std::slice::Iter<'a, (String, JSON)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a JSON);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+pub struct IterMut<'a>(std::slice::IterMut<'a, (String, JSON)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut JSON);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a JSON);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut JSON);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, JSON);
+    type IntoIter = std::vec::IntoIter<(String, JSON)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
@@ -0,0 +1,78 @@
+use super::{Map, JSON};
+
+impl JSON {
+    // Applies an RFC 7396 merge patch: object members are merged recursively, a
+    // `null` member removes the key and any other patch value replaces the target.
+    pub fn merge_patch(&mut self, patch: &JSON) {
+        let members = match patch {
+            JSON::Object(members) => members,
+            _ => {
+                *self = patch.clone();
+                return;
+            }
+        };
+        if !matches!(self, JSON::Object(_)) {
+            *self = JSON::Object(Map::new());
+        }
+        let target = match self {
+            JSON::Object(target) => target,
+            _ => unreachable!("Replaced with an object above"),
+        };
+        for (key, value) in members {
+            match (value, target.get_mut(key)) {
+                (JSON::Null, _) => {
+                    target.remove(key);
+                }
+                (_, Some(existing)) => existing.merge_patch(value),
+                (_, None) => {
+                    let mut added = JSON::Null;
+                    added.merge_patch(value);
+                    target.insert(key.clone(), added);
+                }
+            }
+        }
+    }
+
+    // The smallest merge patch that turns `from` into `to`. Merge patches can't
+    // set a member to `null`, so such members of `to` are left out.
+    pub fn merge_diff(from: &JSON, to: &JSON) -> JSON {
+        let (from, to) = match (from, to) {
+            (JSON::Object(from), JSON::Object(to)) => (from, to),
+            _ => return without_nulls(to),
+        };
+        let mut patch = Map::new();
+        for key in from.keys().filter(|key| !to.contains_key(key)) {
+            patch.insert(key.clone(), JSON::Null);
+        }
+        for (key, value) in to {
+            match from.get(key) {
+                Some(old) if old == value => (),
+                Some(old) => match JSON::merge_diff(old, value) {
+                    // Objects that differ only in `null` members need no patch
+                    JSON::Object(sub) if sub.is_empty() && matches!(old, JSON::Object(_)) => (),
+                    sub => {
+                        patch.insert(key.clone(), sub);
+                    }
+                },
+                None if *value == JSON::Null => (),
+                None => {
+                    patch.insert(key.clone(), without_nulls(value));
+                }
+            }
+        }
+        JSON::Object(patch)
+    }
+}
+
+// Applying a patch drops `null` object members at any depth
+fn without_nulls(value: &JSON) -> JSON {
+    match value {
+        JSON::Object(o) => JSON::Object(
+            o.iter()
+                .filter(|(_, v)| **v != JSON::Null)
+                .map(|(k, v)| (k.clone(), without_nulls(v)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter, Write},
+    str::FromStr,
+};
+
+use crate::parsing::{numbers::scan_number, ErrorKind, JSONError, Position};
+
+// A JSON number. Integers that fit in an i64/u64 are kept exactly; with the
+// `arbitrary_precision` feature any other number keeps its original decimal text.
+#[derive(Clone, Debug)]
+pub struct Number {
+    n: N,
+}
+
+#[derive(Clone, Debug)]
+enum N {
+    PosInt(u64),
+    NegInt(i64),
+    Float(f64),
+    #[cfg(feature = "arbitrary_precision")]
+    Text(String),
+}
+
+impl Number {
+    pub fn from_f64(f: f64) -> Number {
+        Number { n: N::Float(f) }
+    }
+
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    pub fn is_f64(&self) -> bool {
+        match self.n {
+            N::Float(_) => true,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Text(_) => !self.is_i64() && !self.is_u64(),
+            _ => false,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match &self.n {
+            N::PosInt(u) => i64::try_from(*u).ok(),
+            N::NegInt(i) => Some(*i),
+            N::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Text(t) => t.parse().ok(),
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match &self.n {
+            N::PosInt(u) => Some(*u),
+            N::NegInt(_) | N::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Text(t) => t.parse().ok(),
+        }
+    }
+
+    // Always succeeds, but may round integers above 2^53
+    pub fn as_f64(&self) -> Option<f64> {
+        match &self.n {
+            N::PosInt(u) => Some(*u as f64),
+            N::NegInt(i) => Some(*i as f64),
+            N::Float(f) => Some(*f),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Text(t) => t.parse().ok(),
+        }
+    }
+
+    // False only for NaN and infinities, which JSON has no way to write
+    pub fn is_finite(&self) -> bool {
+        match self.n {
+            N::Float(f) => f.is_finite(),
+            _ => true,
+        }
+    }
+
+    // The exact decimal text this number was parsed from, if it was kept
+    #[cfg(feature = "arbitrary_precision")]
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.n {
+            N::Text(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    // `s` must already match the JSON number grammar. Without
+    // `arbitrary_precision`, `None` if it is too large to be an f64.
+    pub(crate) fn from_validated_str(s: &str) -> Option<Number> {
+        let is_integer = !s.contains(['.', 'e', 'E']);
+        if is_integer {
+            if let Ok(u) = s.parse::<u64>() {
+                return Some(Number { n: N::PosInt(u) });
+            }
+            if let Ok(i) = s.parse::<i64>() {
+                if i != 0 {
+                    return Some(Number { n: N::NegInt(i) });
+                }
+            }
+        }
+
+        #[cfg(feature = "arbitrary_precision")]
+        {
+            Some(Number {
+                n: N::Text(s.to_string()),
+            })
+        }
+        #[cfg(not(feature = "arbitrary_precision"))]
+        {
+            let f: f64 = s.parse().expect("Validated number");
+            f.is_finite().then(|| Number::from_f64(f))
+        }
+    }
+
+    // Exact integer value, also for floats with no fractional part
+    fn integer(&self) -> Option<i128> {
+        if let Some(i) = self.as_i64() {
+            return Some(i as i128);
+        }
+        if let Some(u) = self.as_u64() {
+            return Some(u as i128);
+        }
+        let f = self.as_f64()?;
+        if f.fract() == 0.0 && f.abs() < 2f64.powi(100) {
+            Some(f as i128)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = JSONError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match scan_number(s) {
+            Ok(()) => Number::from_validated_str(s).ok_or(JSONError::ParseError(
+                ErrorKind::NumberOutOfRange,
+                Position::new(1, 1, 0),
+            )),
+            Err(_) if s.is_empty() => Err(JSONError::UnexpectedEndOfInput(Position::new(1, 1, 0))),
+            Err(offset) => Err(JSONError::UnexpectedCharacter(
+                s[offset..].chars().next().expect("In bounds"),
+                Position::new(1, offset + 1, offset),
+            )),
+        }
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match &self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            N::Float(fl) if fl.is_nan() => f.write_str("NaN"),
+            N::Float(fl) if fl.is_infinite() => {
+                f.write_str(if *fl > 0.0 { "Infinity" } else { "-Infinity" })
+            }
+            // Written as integers these would read back as a different, exact
+            // integer, so they keep a `.0` to stay floats
+            N::Float(fl) if (2f64.powi(53)..1e21).contains(&fl.abs()) => {
+                write_f64(f, *fl)?;
+                f.write_str(".0")
+            }
+            N::Float(fl) => write_f64(f, *fl),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+// Writes a finite f64 with the fewest digits that parse back to the same value,
+// in plain notation from 1e-6 up to 1e21 and as `1.5e+21` outside of that range,
+// the same layout ECMAScript uses.
+pub(crate) fn write_f64<W: Write + ?Sized>(w: &mut W, f: f64) -> std::fmt::Result {
+    if f == 0.0 {
+        return w.write_str(if f.is_sign_negative() { "-0" } else { "0" });
+    }
+    if f < 0.0 {
+        w.write_char('-')?;
+    }
+    // Rust's `{:e}` finds how many digits are needed to round trip. When two
+    // candidates of that length are equally short, ECMAScript wants the closer
+    // one, which is what rounding the exact value to that many digits gives.
+    let shortest = format!("{:e}", f.abs());
+    let k = shortest
+        .bytes()
+        .take_while(|b| *b != b'e')
+        .filter(u8::is_ascii_digit)
+        .count();
+    let scientific = format!("{:.*e}", k - 1, f.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("Exponent always present");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = k as i32;
+    let n = exponent.parse::<i32>().expect("Integer exponent") + 1;
+
+    if k <= n && n <= 21 {
+        w.write_str(&digits)?;
+        (k..n).try_for_each(|_| w.write_char('0'))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        write!(w, "{}.{}", int, frac)
+    } else if -6 < n && n <= 0 {
+        w.write_str("0.")?;
+        (n..0).try_for_each(|_| w.write_char('0'))?;
+        w.write_str(&digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        w.write_str(first)?;
+        if !rest.is_empty() {
+            write!(w, ".{}", rest)?;
+        }
+        write!(w, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs())
+    }
+}
+
+// Numbers compare by value, so `1`, `1.0` and `1e0` are all equal. With
+// `arbitrary_precision`, decimal text is compared digit by digit against text
+// and integers, and only rounded to f64 against a float.
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(ordering) = self.cmp_text(other) {
+            return ordering == Ordering::Equal;
+        }
+        match (self.integer(), other.integer()) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => self.as_f64() == other.as_f64(),
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(ordering) = self.cmp_text(other) {
+            return Some(ordering);
+        }
+        match (self.integer(), other.integer()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl Number {
+    // The exact order of two numbers when one is text and neither is a float
+    fn cmp_text(&self, other: &Number) -> Option<Ordering> {
+        match (&self.n, &other.n) {
+            (N::Float(_), _) | (_, N::Float(_)) => None,
+            (N::Text(_), _) | (_, N::Text(_)) => {
+                Some(Decimal::new(&self.to_string()).cmp(&Decimal::new(&other.to_string())))
+            }
+            _ => None,
+        }
+    }
+}
+
+// A number in JSON syntax as `0.digits * 10^exponent`, with no leading or
+// trailing zeros in `digits`, which is empty for zero
+#[cfg(feature = "arbitrary_precision")]
+struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl Decimal {
+    fn new(s: &str) -> Decimal {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, parse_exponent(exponent)),
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all = [int, frac].concat();
+        let significant = all.trim_start_matches('0');
+        let leading = (all.len() - significant.len()) as i64;
+        let digits = significant.trim_end_matches('0').to_string();
+        let exponent = if digits.is_empty() {
+            0
+        } else {
+            exponent.saturating_add(int.len() as i64 - leading)
+        };
+        Decimal {
+            negative: negative && !digits.is_empty(),
+            digits,
+            exponent,
+        }
+    }
+
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let sign = |d: &Decimal| match (d.digits.is_empty(), d.negative) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        };
+        let magnitude = || {
+            self.exponent
+                .cmp(&other.exponent)
+                .then_with(|| self.digits.cmp(&other.digits))
+        };
+        match sign(self).cmp(&sign(other)) {
+            Ordering::Equal if self.negative => magnitude().reverse(),
+            Ordering::Equal => magnitude(),
+            ordering => ordering,
+        }
+    }
+}
+
+// Saturates, which keeps the order of exponents too large for an i64
+#[cfg(feature = "arbitrary_precision")]
+fn parse_exponent(s: &str) -> i64 {
+    let (negative, digits) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let n = digits.bytes().fold(0i64, |n, b| {
+        n.saturating_mul(10).saturating_add((b - b'0') as i64)
+    });
+    if negative {
+        -n
+    } else {
+        n
+    }
+}
+
+impl PartialEq<f64> for Number {
+    fn eq(&self, other: &f64) -> bool {
+        *self == Number::from_f64(*other)
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Self {
+        Number::from_f64(value)
+    }
+}
+
+impl From<f32> for Number {
+    fn from(value: f32) -> Self {
+        Number::from_f64(value as f64)
+    }
+}
+
+macro_rules! from_unsigned {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Number {
+            fn from(value: $t) -> Self {
+                Number { n: N::PosInt(value as u64) }
+            }
+        }
+    )*};
+}
+
+macro_rules! from_signed {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Number {
+            fn from(value: $t) -> Self {
+                if value < 0 {
+                    Number { n: N::NegInt(value as i64) }
+                } else {
+                    Number { n: N::PosInt(value as u64) }
+                }
+            }
+        }
+    )*};
+}
+
+from_unsigned!(u8, u16, u32, u64, usize);
+from_signed!(i8, i16, i32, i64, isize);
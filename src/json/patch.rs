@@ -0,0 +1,272 @@
+use std::fmt::{Display, Formatter};
+
+use super::{
+    pointer::{self, push_token},
+    Map, PointerError, JSON,
+};
+
+// Errors name the index of the failing operation in the patch
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchError {
+    InvalidPatch(&'static str),
+    InvalidOperation(usize, &'static str),
+    Pointer(usize, PointerError),
+    TestFailed(usize, String),
+    MoveIntoChild(usize, String),
+}
+
+impl PatchError {
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            PatchError::InvalidPatch(_) => None,
+            PatchError::InvalidOperation(i, _)
+            | PatchError::Pointer(i, _)
+            | PatchError::TestFailed(i, _)
+            | PatchError::MoveIntoChild(i, _) => Some(*i),
+        }
+    }
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PatchError::InvalidPatch(s) => write!(f, "Invalid patch: {}", s),
+            PatchError::InvalidOperation(i, s) => write!(f, "Operation {}: {}", i, s),
+            PatchError::Pointer(i, e) => write!(f, "Operation {}: {}", i, e),
+            PatchError::TestFailed(i, p) => {
+                write!(f, "Operation {}: test failed at \"{}\"", i, p)
+            }
+            PatchError::MoveIntoChild(i, p) => write!(
+                f,
+                "Operation {}: cannot move \"{}\" into one of its own children",
+                i, p
+            ),
+        }
+    }
+}
+
+// Paths and `from` are JSON pointers
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Add { path: String, value: JSON },
+    Remove { path: String },
+    Replace { path: String, value: JSON },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JSON },
+}
+
+// An RFC 6902 JSON Patch, applied as a whole or not at all
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch(pub Vec<Operation>);
+
+impl Patch {
+    pub fn new() -> Patch {
+        Patch(Vec::new())
+    }
+
+    // Reads a patch document, `[{"op": "add", "path": "/a", "value": 1}, ...]`.
+    // Members other than the ones an operation needs are ignored.
+    pub fn from_json(json: &JSON) -> Result<Patch, PatchError> {
+        let ops = match json {
+            JSON::Array(ops) => ops,
+            _ => return Err(PatchError::InvalidPatch("Patch must be an array")),
+        };
+        ops.iter()
+            .enumerate()
+            .map(|(i, op)| read_operation(i, op))
+            .collect::<Result<_, _>>()
+            .map(Patch)
+    }
+
+    pub fn to_json(&self) -> JSON {
+        JSON::Array(self.0.iter().map(operation_to_json).collect())
+    }
+
+    // Applies every operation in order. If one fails, `doc` is left as it was.
+    pub fn apply(&self, doc: &mut JSON) -> Result<(), PatchError> {
+        let mut patched = doc.clone();
+        for (i, op) in self.0.iter().enumerate() {
+            apply_operation(&mut patched, i, op)?;
+        }
+        *doc = patched;
+        Ok(())
+    }
+
+    // Generates a patch that turns `from` into `to`. Objects and arrays are
+    // compared member by member, anything else that differs is replaced.
+    pub fn diff(from: &JSON, to: &JSON) -> Patch {
+        let mut ops = Vec::new();
+        diff_values(&mut ops, &mut String::new(), from, to);
+        Patch(ops)
+    }
+}
+
+fn read_operation(i: usize, op: &JSON) -> Result<Operation, PatchError> {
+    let op = match op {
+        JSON::Object(o) => o,
+        _ => {
+            return Err(PatchError::InvalidOperation(
+                i,
+                "Operation must be an object",
+            ))
+        }
+    };
+    let pointer_member = |name: &'static str| -> Result<String, PatchError> {
+        let missing = match name {
+            "from" => "Missing or non-string \"from\"",
+            _ => "Missing or non-string \"path\"",
+        };
+        let p = op
+            .get(name)
+            .and_then(JSON::as_string)
+            .ok_or(PatchError::InvalidOperation(i, missing))?;
+        pointer::parse(p).map_err(|e| PatchError::Pointer(i, e))?;
+        Ok(p.to_string())
+    };
+    let value = || {
+        op.get("value")
+            .cloned()
+            .ok_or(PatchError::InvalidOperation(i, "Missing \"value\""))
+    };
+
+    let name = op
+        .get("op")
+        .and_then(JSON::as_string)
+        .ok_or(PatchError::InvalidOperation(
+            i,
+            "Missing or non-string \"op\"",
+        ))?;
+    Ok(match name {
+        "add" => Operation::Add {
+            path: pointer_member("path")?,
+            value: value()?,
+        },
+        "remove" => Operation::Remove {
+            path: pointer_member("path")?,
+        },
+        "replace" => Operation::Replace {
+            path: pointer_member("path")?,
+            value: value()?,
+        },
+        "move" => Operation::Move {
+            from: pointer_member("from")?,
+            path: pointer_member("path")?,
+        },
+        "copy" => Operation::Copy {
+            from: pointer_member("from")?,
+            path: pointer_member("path")?,
+        },
+        "test" => Operation::Test {
+            path: pointer_member("path")?,
+            value: value()?,
+        },
+        _ => return Err(PatchError::InvalidOperation(i, "Unknown \"op\"")),
+    })
+}
+
+fn operation_to_json(op: &Operation) -> JSON {
+    let mut o = Map::with_capacity(3);
+    let (name, from, path, value) = match op {
+        Operation::Add { path, value } => ("add", None, path, Some(value)),
+        Operation::Remove { path } => ("remove", None, path, None),
+        Operation::Replace { path, value } => ("replace", None, path, Some(value)),
+        Operation::Move { from, path } => ("move", Some(from), path, None),
+        Operation::Copy { from, path } => ("copy", Some(from), path, None),
+        Operation::Test { path, value } => ("test", None, path, Some(value)),
+    };
+    o.insert("op".to_string(), JSON::from(name));
+    if let Some(from) = from {
+        o.insert("from".to_string(), JSON::from(from));
+    }
+    o.insert("path".to_string(), JSON::from(path));
+    if let Some(value) = value {
+        o.insert("value".to_string(), value.clone());
+    }
+    JSON::Object(o)
+}
+
+fn apply_operation(doc: &mut JSON, i: usize, op: &Operation) -> Result<(), PatchError> {
+    let pointer_error = |e| PatchError::Pointer(i, e);
+    match op {
+        Operation::Add { path, value } => {
+            doc.insert_at(path, value.clone(), false)
+                .map_err(pointer_error)?;
+        }
+        Operation::Remove { path } => {
+            doc.remove_at(path).map_err(pointer_error)?;
+        }
+        Operation::Replace { path, value } => {
+            *doc.pointer_mut(path).map_err(pointer_error)? = value.clone();
+        }
+        Operation::Move { from, path } => {
+            if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                return Err(PatchError::MoveIntoChild(i, from.clone()));
+            }
+            let value = doc.remove_at(from).map_err(pointer_error)?;
+            doc.insert_at(path, value, false).map_err(pointer_error)?;
+        }
+        Operation::Copy { from, path } => {
+            let value = doc.pointer(from).map_err(pointer_error)?.clone();
+            doc.insert_at(path, value, false).map_err(pointer_error)?;
+        }
+        Operation::Test { path, value } => {
+            if doc.pointer(path).map_err(pointer_error)? != value {
+                return Err(PatchError::TestFailed(i, path.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn diff_values(ops: &mut Vec<Operation>, path: &mut String, from: &JSON, to: &JSON) {
+    if from == to {
+        return;
+    }
+    let len = path.len();
+    match (from, to) {
+        (JSON::Object(a), JSON::Object(b)) => {
+            for (key, old) in a {
+                push_token(path, key);
+                match b.get(key) {
+                    Some(new) => diff_values(ops, path, old, new),
+                    None => ops.push(Operation::Remove { path: path.clone() }),
+                }
+                path.truncate(len);
+            }
+            for (key, new) in b.iter().filter(|(key, _)| !a.contains_key(key)) {
+                push_token(path, key);
+                ops.push(Operation::Add {
+                    path: path.clone(),
+                    value: new.clone(),
+                });
+                path.truncate(len);
+            }
+        }
+        (JSON::Array(a), JSON::Array(b)) => {
+            for (i, (old, new)) in a.iter().zip(b).enumerate() {
+                push_token(path, &i.to_string());
+                diff_values(ops, path, old, new);
+                path.truncate(len);
+            }
+            // Removed from the back so earlier indices stay valid
+            for i in (b.len()..a.len()).rev() {
+                push_token(path, &i.to_string());
+                ops.push(Operation::Remove { path: path.clone() });
+                path.truncate(len);
+            }
+            for (i, new) in b.iter().enumerate().skip(a.len()) {
+                push_token(path, &i.to_string());
+                ops.push(Operation::Add {
+                    path: path.clone(),
+                    value: new.clone(),
+                });
+                path.truncate(len);
+            }
+        }
+        _ => ops.push(Operation::Replace {
+            path: path.clone(),
+            value: to.clone(),
+        }),
+    }
+}
@@ -0,0 +1,713 @@
+use std::{
+    fmt::{Display, Formatter, Write},
+    str::FromStr,
+};
+
+use super::{Number, JSON};
+use crate::parsing::{strings, JSONError, Position};
+
+// Largest integer JSONPath allows in indices and slices (I-JSON's exact range)
+const MAX_INTEGER: u64 = (1 << 53) - 1;
+
+// Offsets are byte offsets into the query string
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathError {
+    UnexpectedCharacter(char, usize),
+    UnexpectedEndOfInput,
+    InvalidSyntax(&'static str, usize),
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PathError::UnexpectedCharacter(c, p) => {
+                write!(f, "Unexpected character '{}' at offset {}", c, p)
+            }
+            PathError::UnexpectedEndOfInput => write!(f, "Unexpected end of JSONPath query"),
+            PathError::InvalidSyntax(s, p) => write!(f, "{} at offset {}", s, p),
+        }
+    }
+}
+
+// A parsed RFC 9535 JSONPath query, e.g. `$.users[*].friends[?@.hobbies].name`.
+// Function extensions (`length()`, `match()`, ...) are not supported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JSONPath {
+    segments: Vec<Segment>,
+}
+
+// A node selected by a query: the value and its normalized path, like `$['users'][0]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathMatch<'a> {
+    pub path: String,
+    pub value: &'a JSON,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Exists(Query),
+    Compare(Comparable, CompareOp, Comparable),
+}
+
+// `@...` inside a filter is relative to the node being tested, `$...` to the root
+#[derive(Clone, Debug, PartialEq)]
+struct Query {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Comparable {
+    Literal(JSON),
+    Query(Query),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl JSONPath {
+    pub fn query<'a>(&self, value: &'a JSON) -> Vec<PathMatch<'a>> {
+        let root = PathMatch {
+            path: "$".to_string(),
+            value,
+        };
+        select(&self.segments, root, value)
+    }
+
+    // Like `query` without building the normalized paths
+    pub fn values<'a>(&self, value: &'a JSON) -> Vec<&'a JSON> {
+        select(&self.segments, value, value)
+    }
+}
+
+impl FromStr for JSONPath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s, pos: 0 };
+        parser.expect('$')?;
+        let segments = parser.segments()?;
+        if parser.pos != s.len() {
+            return Err(parser.unexpected());
+        }
+        Ok(JSONPath { segments })
+    }
+}
+
+impl JSON {
+    // Runs a JSONPath query, returning every match in document order
+    pub fn query(&self, path: &str) -> Result<Vec<PathMatch<'_>>, PathError> {
+        Ok(path.parse::<JSONPath>()?.query(self))
+    }
+}
+
+struct Parser<'s> {
+    s: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.s[self.pos..].starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathError> {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> PathError {
+        match self.peek() {
+            Some(c) => PathError::UnexpectedCharacter(c, self.pos),
+            None => PathError::UnexpectedEndOfInput,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    // Whitespace is allowed between segments but not after the last one
+    fn segments(&mut self) -> Result<Vec<Segment>, PathError> {
+        let mut segments = Vec::new();
+        loop {
+            let before = self.pos;
+            self.skip_whitespace();
+            match self.peek() {
+                Some('.' | '[') => segments.push(self.segment()?),
+                _ => {
+                    self.pos = before;
+                    return Ok(segments);
+                }
+            }
+        }
+    }
+
+    fn segment(&mut self) -> Result<Segment, PathError> {
+        if self.eat("..") {
+            let selectors = match self.peek() {
+                Some('[') => self.bracketed()?,
+                Some('*') => {
+                    self.pos += 1;
+                    vec![Selector::Wildcard]
+                }
+                _ => vec![Selector::Name(self.member_name()?)],
+            };
+            Ok(Segment::Descendant(selectors))
+        } else if self.eat(".") {
+            if self.eat("*") {
+                Ok(Segment::Child(vec![Selector::Wildcard]))
+            } else {
+                Ok(Segment::Child(vec![Selector::Name(self.member_name()?)]))
+            }
+        } else {
+            Ok(Segment::Child(self.bracketed()?))
+        }
+    }
+
+    fn member_name(&mut self) -> Result<String, PathError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() => (),
+            _ => return Err(self.unexpected()),
+        }
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        Ok(self.s[start..self.pos].to_string())
+    }
+
+    fn bracketed(&mut self) -> Result<Vec<Selector>, PathError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string_literal()?)),
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.logical_or()?))
+            }
+            _ => self.index_or_slice(),
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, PathError> {
+        let start = self.integer()?;
+        self.skip_whitespace();
+        if !self.eat(":") {
+            return match start {
+                Some(i) => Ok(Selector::Index(i)),
+                None => Err(self.unexpected()),
+            };
+        }
+        self.skip_whitespace();
+        let end = self.integer()?;
+        self.skip_whitespace();
+        let step = if self.eat(":") {
+            self.skip_whitespace();
+            self.integer()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    // An optional integer without leading zeros, `-0` or values beyond ±(2^53 - 1)
+    fn integer(&mut self) -> Result<Option<i64>, PathError> {
+        let start = self.pos;
+        self.eat("-");
+        let digits = self.pos;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+        if self.pos == digits {
+            return if start == digits {
+                Ok(None)
+            } else {
+                Err(self.unexpected())
+            };
+        }
+        let text = &self.s[start..self.pos];
+        if self.s[digits..].starts_with('0') && (self.pos - digits > 1 || start != digits) {
+            return Err(PathError::InvalidSyntax("Invalid integer", start));
+        }
+        match text.parse::<i64>() {
+            Ok(i) if i.unsigned_abs() <= MAX_INTEGER => Ok(Some(i)),
+            _ => Err(PathError::InvalidSyntax("Integer out of range", start)),
+        }
+    }
+
+    fn string_literal(&mut self) -> Result<String, PathError> {
+        let quote = self.peek().ok_or(PathError::UnexpectedEndOfInput)?;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = self.peek().ok_or(PathError::UnexpectedEndOfInput)?;
+            if c == quote {
+                self.pos += 1;
+                return Ok(out);
+            }
+            if c < '\x20' {
+                return Err(self.unexpected());
+            }
+            if c != '\\' {
+                out.push(c);
+                self.pos += c.len_utf8();
+                continue;
+            }
+
+            let escape_start = self.pos;
+            self.pos += 1;
+            let esc = self.peek().ok_or(PathError::UnexpectedEndOfInput)?;
+            self.pos += esc.len_utf8();
+            match esc {
+                'b' => out.push('\x08'),
+                'f' => out.push('\x0c'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                '/' | '\\' => out.push(esc),
+                e if e == quote => out.push(e),
+                'u' => {
+                    let at = Position::new(1, escape_start + 1, escape_start);
+                    let (c, len) = strings::decode_unicode_escape(self.s, escape_start, at)
+                        .map_err(|e| match e {
+                            JSONError::UnexpectedCharacter(c, p) => {
+                                PathError::UnexpectedCharacter(c, p.offset)
+                            }
+                            JSONError::LoneSurrogate(..) => {
+                                PathError::InvalidSyntax("Unpaired surrogate", escape_start)
+                            }
+                            _ => PathError::UnexpectedEndOfInput,
+                        })?;
+                    out.push(c);
+                    self.pos = escape_start + len;
+                }
+                _ => {
+                    return Err(PathError::InvalidSyntax(
+                        "Invalid escape sequence",
+                        escape_start,
+                    ))
+                }
+            }
+        }
+    }
+
+    fn logical_or(&mut self) -> Result<Expr, PathError> {
+        let mut terms = vec![self.logical_and()?];
+        self.skip_whitespace();
+        while self.eat("||") {
+            self.skip_whitespace();
+            terms.push(self.logical_and()?);
+            self.skip_whitespace();
+        }
+        Ok(match terms.len() {
+            1 => terms.pop().expect("One term"),
+            _ => Expr::Or(terms),
+        })
+    }
+
+    fn logical_and(&mut self) -> Result<Expr, PathError> {
+        let mut terms = vec![self.basic()?];
+        self.skip_whitespace();
+        while self.eat("&&") {
+            self.skip_whitespace();
+            terms.push(self.basic()?);
+            self.skip_whitespace();
+        }
+        Ok(match terms.len() {
+            1 => terms.pop().expect("One term"),
+            _ => Expr::And(terms),
+        })
+    }
+
+    fn basic(&mut self) -> Result<Expr, PathError> {
+        if self.eat("!") {
+            self.skip_whitespace();
+            let negated = if self.peek() == Some('(') {
+                self.parenthesized()?
+            } else {
+                match self.peek() {
+                    Some('@' | '$') => Expr::Exists(self.filter_query()?),
+                    _ => return Err(self.unexpected()),
+                }
+            };
+            return Ok(Expr::Not(Box::new(negated)));
+        }
+        if self.peek() == Some('(') {
+            return self.parenthesized();
+        }
+
+        let start = self.pos;
+        let left = self.comparable()?;
+        self.skip_whitespace();
+        let op = match self.comparison_op() {
+            Some(op) => op,
+            None => {
+                return match left {
+                    Comparable::Query(q) => Ok(Expr::Exists(q)),
+                    Comparable::Literal(_) => Err(PathError::InvalidSyntax(
+                        "Literal used without a comparison",
+                        start,
+                    )),
+                }
+            }
+        };
+        self.skip_whitespace();
+        let right_start = self.pos;
+        let right = self.comparable()?;
+        for (side, offset) in [(&left, start), (&right, right_start)] {
+            if let Comparable::Query(q) = side {
+                if !q.is_singular() {
+                    return Err(PathError::InvalidSyntax(
+                        "Comparison with a query that can select more than one value",
+                        offset,
+                    ));
+                }
+            }
+        }
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn parenthesized(&mut self) -> Result<Expr, PathError> {
+        self.expect('(')?;
+        self.skip_whitespace();
+        let expr = self.logical_or()?;
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(expr)
+    }
+
+    fn filter_query(&mut self) -> Result<Query, PathError> {
+        let relative = self.peek() == Some('@');
+        self.pos += 1;
+        Ok(Query {
+            relative,
+            segments: self.segments()?,
+        })
+    }
+
+    fn comparison_op(&mut self) -> Option<CompareOp> {
+        let ops = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+        ops.into_iter()
+            .find(|(text, _)| self.eat(text))
+            .map(|(_, op)| op)
+    }
+
+    fn comparable(&mut self) -> Result<Comparable, PathError> {
+        let start = self.pos;
+        match self.peek() {
+            Some('@' | '$') => Ok(Comparable::Query(self.filter_query()?)),
+            Some('\'' | '"') => Ok(Comparable::Literal(JSON::String(self.string_literal()?))),
+            Some('-' | '0'..='9') => {
+                while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+                    self.pos += 1;
+                }
+                match self.s[start..self.pos].parse::<Number>() {
+                    Ok(n) => Ok(Comparable::Literal(JSON::Number(n))),
+                    Err(_) => Err(PathError::InvalidSyntax("Invalid number", start)),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+                match &self.s[start..self.pos] {
+                    "true" => Ok(Comparable::Literal(JSON::Bool(true))),
+                    "false" => Ok(Comparable::Literal(JSON::Bool(false))),
+                    "null" => Ok(Comparable::Literal(JSON::Null)),
+                    _ if self.peek() == Some('(') => Err(PathError::InvalidSyntax(
+                        "Function expressions are not supported",
+                        start,
+                    )),
+                    _ => Err(PathError::UnexpectedCharacter(c, start)),
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+impl Query {
+    // A query that selects at most one node: only name and index selectors
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child(selectors) => {
+                matches!(
+                    selectors.as_slice(),
+                    [Selector::Name(_)] | [Selector::Index(_)]
+                )
+            }
+            Segment::Descendant(_) => false,
+        })
+    }
+
+    fn evaluate<'a>(&self, current: &'a JSON, root: &'a JSON) -> Vec<&'a JSON> {
+        let start = if self.relative { current } else { root };
+        select(&self.segments, start, root)
+    }
+}
+
+impl Expr {
+    fn test(&self, current: &JSON, root: &JSON) -> bool {
+        match self {
+            Expr::Or(terms) => terms.iter().any(|t| t.test(current, root)),
+            Expr::And(terms) => terms.iter().all(|t| t.test(current, root)),
+            Expr::Not(expr) => !expr.test(current, root),
+            Expr::Exists(query) => !query.evaluate(current, root).is_empty(),
+            Expr::Compare(left, op, right) => {
+                let a = left.evaluate(current, root);
+                let b = right.evaluate(current, root);
+                match op {
+                    CompareOp::Eq => a == b,
+                    CompareOp::Ne => a != b,
+                    CompareOp::Lt => less(a, b),
+                    CompareOp::Le => less(a, b) || a == b,
+                    CompareOp::Gt => less(b, a),
+                    CompareOp::Ge => less(b, a) || a == b,
+                }
+            }
+        }
+    }
+}
+
+impl Comparable {
+    // `None` when a query selects nothing, which only equals another `None`
+    fn evaluate<'a>(&'a self, current: &'a JSON, root: &'a JSON) -> Option<&'a JSON> {
+        match self {
+            Comparable::Literal(value) => Some(value),
+            Comparable::Query(query) => query.evaluate(current, root).into_iter().next(),
+        }
+    }
+}
+
+// Only numbers and strings are ordered; any other pair is neither less nor greater
+fn less(a: Option<&JSON>, b: Option<&JSON>) -> bool {
+    match (a, b) {
+        (Some(JSON::Number(a)), Some(JSON::Number(b))) => a < b,
+        (Some(JSON::String(a)), Some(JSON::String(b))) => a < b,
+        _ => false,
+    }
+}
+
+// Lets selection build either bare references or references with their paths
+trait Node<'a> {
+    fn value(&self) -> &'a JSON;
+    fn member(&self, key: &str, value: &'a JSON) -> Self;
+    fn element(&self, index: usize, value: &'a JSON) -> Self;
+}
+
+impl<'a> Node<'a> for &'a JSON {
+    fn value(&self) -> &'a JSON {
+        self
+    }
+
+    fn member(&self, _: &str, value: &'a JSON) -> Self {
+        value
+    }
+
+    fn element(&self, _: usize, value: &'a JSON) -> Self {
+        value
+    }
+}
+
+impl<'a> Node<'a> for PathMatch<'a> {
+    fn value(&self) -> &'a JSON {
+        self.value
+    }
+
+    fn member(&self, key: &str, value: &'a JSON) -> Self {
+        let mut path = String::with_capacity(self.path.len() + key.len() + 4);
+        path.push_str(&self.path);
+        write_normalized_name(&mut path, key);
+        PathMatch { path, value }
+    }
+
+    fn element(&self, index: usize, value: &'a JSON) -> Self {
+        PathMatch {
+            path: format!("{}[{}]", self.path, index),
+            value,
+        }
+    }
+}
+
+// Names in normalized paths use single quotes, escaping `'`, `\` and control characters
+fn write_normalized_name(path: &mut String, name: &str) {
+    path.push_str("['");
+    for c in name.chars() {
+        match c {
+            '\'' => path.push_str("\\'"),
+            '\\' => path.push_str("\\\\"),
+            '\x08' => path.push_str("\\b"),
+            '\x0c' => path.push_str("\\f"),
+            '\n' => path.push_str("\\n"),
+            '\r' => path.push_str("\\r"),
+            '\t' => path.push_str("\\t"),
+            c if c < '\x20' => {
+                write!(path, "\\u{:04x}", c as u32).expect("Writing to a String");
+            }
+            c => path.push(c),
+        }
+    }
+    path.push_str("']");
+}
+
+fn select<'a, N: Node<'a>>(segments: &[Segment], start: N, root: &'a JSON) -> Vec<N> {
+    let mut nodes = vec![start];
+    for segment in segments {
+        let mut next = Vec::new();
+        for node in &nodes {
+            match segment {
+                Segment::Child(selectors) => apply(selectors, node, root, &mut next),
+                Segment::Descendant(selectors) => descend(selectors, node, root, &mut next),
+            }
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+// Applies the selectors to the node and then to each of its descendants, in document order
+fn descend<'a, N: Node<'a>>(selectors: &[Selector], node: &N, root: &'a JSON, out: &mut Vec<N>) {
+    apply(selectors, node, root, out);
+    for_each_child(node, |child| descend(selectors, &child, root, out));
+}
+
+fn apply<'a, N: Node<'a>>(selectors: &[Selector], node: &N, root: &'a JSON, out: &mut Vec<N>) {
+    for selector in selectors {
+        match (selector, node.value()) {
+            (Selector::Name(name), JSON::Object(o)) => {
+                if let Some(value) = o.get(name) {
+                    out.push(node.member(name, value));
+                }
+            }
+            (Selector::Wildcard, _) => for_each_child(node, |child| out.push(child)),
+            (Selector::Index(i), JSON::Array(a)) => {
+                let index = if *i < 0 { a.len() as i64 + i } else { *i };
+                if (0..a.len() as i64).contains(&index) {
+                    out.push(node.element(index as usize, &a[index as usize]));
+                }
+            }
+            (Selector::Slice(start, end, step), JSON::Array(a)) => {
+                for i in slice_indices(*start, *end, *step, a.len()) {
+                    out.push(node.element(i, &a[i]));
+                }
+            }
+            (Selector::Filter(expr), _) => for_each_child(node, |child| {
+                if expr.test(child.value(), root) {
+                    out.push(child);
+                }
+            }),
+            _ => (),
+        }
+    }
+}
+
+fn for_each_child<'a, N: Node<'a>, F: FnMut(N)>(node: &N, mut f: F) {
+    match node.value() {
+        JSON::Array(a) => a
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| f(node.element(i, v))),
+        JSON::Object(o) => o.iter().for_each(|(k, v)| f(node.member(k, v))),
+        _ => (),
+    }
+}
+
+// RFC 9535 section 2.3.4.2.2: negative bounds count from the end and are
+// clamped to the array, a zero step selects nothing
+fn slice_indices(
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+    len: usize,
+) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+    indices
+}
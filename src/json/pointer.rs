@@ -0,0 +1,254 @@
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter},
+};
+
+use super::{Map, JSON};
+
+// Errors point at the failing reference token by its index in the pointer,
+// so `/a/b/c` failing at `b` reports segment 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PointerError {
+    InvalidPointer(String),
+    KeyNotFound(usize, String),
+    InvalidIndex(usize, String),
+    IndexOutOfBounds(usize, usize, usize),
+    NotAContainer(usize, String),
+}
+
+impl Display for PointerError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PointerError::InvalidPointer(p) => write!(f, "Invalid JSON pointer \"{}\"", p),
+            PointerError::KeyNotFound(i, k) => {
+                write!(f, "Segment {}: key \"{}\" not found in object", i, k)
+            }
+            PointerError::InvalidIndex(i, t) => {
+                write!(f, "Segment {}: \"{}\" is not a valid array index", i, t)
+            }
+            PointerError::IndexOutOfBounds(i, idx, len) => write!(
+                f,
+                "Segment {}: index {} out of bounds for array of length {}",
+                i, idx, len
+            ),
+            PointerError::NotAContainer(i, t) => write!(
+                f,
+                "Segment {}: cannot look up \"{}\" in a value that is not an array or object",
+                i, t
+            ),
+        }
+    }
+}
+
+// Splits a pointer into its unescaped reference tokens. `""` is the whole
+// document and yields no tokens.
+pub fn parse(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PointerError::InvalidPointer(pointer.to_string()));
+    }
+    pointer[1..]
+        .split('/')
+        .map(|token| {
+            unescape(token).ok_or_else(|| PointerError::InvalidPointer(pointer.to_string()))
+        })
+        .collect()
+}
+
+fn unescape(token: &str) -> Option<String> {
+    if !token.contains('~') {
+        return Some(token.to_string());
+    }
+    let mut out = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => out.push('~'),
+            Some('1') => out.push('/'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+// Escapes a single reference token, `~` as `~0` and `/` as `~1`
+pub fn escape(token: &str) -> Cow<'_, str> {
+    if token.contains(['~', '/']) {
+        Cow::Owned(token.replace('~', "~0").replace('/', "~1"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+// Builds a pointer from unescaped reference tokens
+pub fn to_pointer<S: AsRef<str>>(tokens: &[S]) -> String {
+    tokens
+        .iter()
+        .map(|t| format!("/{}", escape(t.as_ref())))
+        .collect()
+}
+
+// Appends one escaped reference token to a pointer being built
+pub(crate) fn push_token(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    pointer.push_str(&escape(token));
+}
+
+fn array_index(segment: usize, token: &str) -> Result<usize, PointerError> {
+    let valid =
+        token == "0" || (!token.starts_with('0') && token.bytes().all(|b| b.is_ascii_digit()));
+    match token.parse() {
+        Ok(i) if valid => Ok(i),
+        _ => Err(PointerError::InvalidIndex(segment, token.to_string())),
+    }
+}
+
+// The container created for a missing member, chosen by the token that follows it
+fn empty_container(next: &str) -> JSON {
+    if next == "-" || next == "0" {
+        JSON::Array(Vec::new())
+    } else {
+        JSON::Object(Map::new())
+    }
+}
+
+fn step<'a>(value: &'a JSON, segment: usize, token: &str) -> Result<&'a JSON, PointerError> {
+    match value {
+        JSON::Object(o) => o
+            .get(token)
+            .ok_or_else(|| PointerError::KeyNotFound(segment, token.to_string())),
+        JSON::Array(a) => {
+            let i = array_index(segment, token)?;
+            a.get(i)
+                .ok_or(PointerError::IndexOutOfBounds(segment, i, a.len()))
+        }
+        _ => Err(PointerError::NotAContainer(segment, token.to_string())),
+    }
+}
+
+fn step_mut<'a>(
+    value: &'a mut JSON,
+    segment: usize,
+    token: &str,
+) -> Result<&'a mut JSON, PointerError> {
+    match value {
+        JSON::Object(o) => o
+            .get_mut(token)
+            .ok_or_else(|| PointerError::KeyNotFound(segment, token.to_string())),
+        JSON::Array(a) => {
+            let i = array_index(segment, token)?;
+            let len = a.len();
+            a.get_mut(i)
+                .ok_or(PointerError::IndexOutOfBounds(segment, i, len))
+        }
+        _ => Err(PointerError::NotAContainer(segment, token.to_string())),
+    }
+}
+
+impl JSON {
+    // Looks up a value by RFC 6901 JSON pointer, e.g. `/users/3/friends/0/name`
+    pub fn pointer(&self, pointer: &str) -> Result<&JSON, PointerError> {
+        parse(pointer)?
+            .iter()
+            .enumerate()
+            .try_fold(self, |value, (i, token)| step(value, i, token))
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<&mut JSON, PointerError> {
+        parse(pointer)?
+            .iter()
+            .enumerate()
+            .try_fold(self, |value, (i, token)| step_mut(value, i, token))
+    }
+
+    // Adds `value` at `pointer`. Object members are inserted or replaced, array
+    // elements are inserted before the given index, and `-` appends to an array.
+    // With `create_missing`, absent intermediate members are created as arrays
+    // when the next token is `0` or `-` and as objects otherwise.
+    // Returns the value replaced in an object, if any.
+    pub fn insert_at(
+        &mut self,
+        pointer: &str,
+        value: JSON,
+        create_missing: bool,
+    ) -> Result<Option<JSON>, PointerError> {
+        let tokens = parse(pointer)?;
+        let (last, parents) = match tokens.split_last() {
+            Some(split) => split,
+            None => return Ok(Some(std::mem::replace(self, value))),
+        };
+
+        let mut target = self;
+        for (i, token) in parents.iter().enumerate() {
+            let mut token = token.as_str();
+            let appended;
+            if create_missing {
+                let next = &tokens[i + 1];
+                match target {
+                    JSON::Object(o) if !o.contains_key(token) => {
+                        o.insert(token.to_string(), empty_container(next));
+                    }
+                    JSON::Array(a) if token == "-" => {
+                        a.push(empty_container(next));
+                        appended = (a.len() - 1).to_string();
+                        token = &appended;
+                    }
+                    _ => (),
+                }
+            }
+            target = step_mut(target, i, token)?;
+        }
+
+        let segment = parents.len();
+        match target {
+            JSON::Object(o) => Ok(o.insert(last.clone(), value)),
+            JSON::Array(a) if last == "-" => {
+                a.push(value);
+                Ok(None)
+            }
+            JSON::Array(a) => {
+                let i = array_index(segment, last)?;
+                if i > a.len() {
+                    return Err(PointerError::IndexOutOfBounds(segment, i, a.len()));
+                }
+                a.insert(i, value);
+                Ok(None)
+            }
+            _ => Err(PointerError::NotAContainer(segment, last.clone())),
+        }
+    }
+
+    // Removes and returns the value at `pointer`, shifting later array elements down
+    pub fn remove_at(&mut self, pointer: &str) -> Result<JSON, PointerError> {
+        let tokens = parse(pointer)?;
+        let (last, parents) = match tokens.split_last() {
+            Some(split) => split,
+            None => return Ok(std::mem::replace(self, JSON::Null)),
+        };
+
+        let target = parents
+            .iter()
+            .enumerate()
+            .try_fold(self, |value, (i, token)| step_mut(value, i, token))?;
+        let segment = parents.len();
+        match target {
+            JSON::Object(o) => o
+                .remove(last)
+                .ok_or_else(|| PointerError::KeyNotFound(segment, last.clone())),
+            JSON::Array(a) => {
+                let i = array_index(segment, last)?;
+                if i >= a.len() {
+                    return Err(PointerError::IndexOutOfBounds(segment, i, a.len()));
+                }
+                Ok(a.remove(i))
+            }
+            _ => Err(PointerError::NotAContainer(segment, last.clone())),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt::{Result, Write};
+
+use super::{escape, JSONWriterError, Number, JSON};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Newline {
+    Lf,
+    CrLf,
+}
+
+// What to write for NaN and infinities, which JSON can't represent. `String`
+// writes them as `"NaN"`, `"Infinity"` and `"-Infinity"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonFinite {
+    Error,
+    Null,
+    String,
+}
+
+// Controls how `JSON::to_string_pretty` lays out a value. With `indent` set to
+// `None` everything is written on a single line; `compact` drops the optional
+// spaces after `,` and `:`. With `json5` the output is JSON5: identifier-like
+// keys are unquoted, strings use whichever quote needs fewer escapes and NaN
+// and infinities are written as-is, ignoring `non_finite`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrettyConfig {
+    pub indent: Option<Indent>,
+    pub newline: Newline,
+    pub compact: bool,
+    pub sort_keys: bool,
+    pub ascii_only: bool,
+    pub non_finite: NonFinite,
+    pub json5: bool,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> PrettyConfig {
+        PrettyConfig {
+            indent: Some(Indent::Spaces(2)),
+            newline: Newline::Lf,
+            compact: false,
+            sort_keys: false,
+            ascii_only: false,
+            non_finite: NonFinite::Null,
+            json5: false,
+        }
+    }
+}
+
+impl PrettyConfig {
+    pub fn new() -> PrettyConfig {
+        PrettyConfig::default()
+    }
+
+    // No whitespace at all: `{"a":[1,2]}`
+    pub fn compact() -> PrettyConfig {
+        PrettyConfig {
+            indent: None,
+            compact: true,
+            ..PrettyConfig::default()
+        }
+    }
+
+    // The single line layout used by `Display`: `{"a": [1, 2]}`
+    pub fn inline() -> PrettyConfig {
+        PrettyConfig {
+            indent: None,
+            ..PrettyConfig::default()
+        }
+    }
+
+    pub(crate) fn write_newline<W: Write + ?Sized>(&self, w: &mut W, depth: usize) -> Result {
+        let indent = match self.indent {
+            Some(indent) => indent,
+            None => return Ok(()),
+        };
+        w.write_str(match self.newline {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        })?;
+        let (unit, width) = match indent {
+            Indent::Spaces(n) => (' ', n),
+            Indent::Tabs(n) => ('\t', n),
+        };
+        for _ in 0..depth * width {
+            w.write_char(unit)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_comma<W: Write + ?Sized>(&self, w: &mut W, depth: usize) -> Result {
+        w.write_char(',')?;
+        if self.indent.is_some() {
+            self.write_newline(w, depth)
+        } else if self.compact {
+            Ok(())
+        } else {
+            w.write_char(' ')
+        }
+    }
+
+    pub(crate) fn write_colon<W: Write + ?Sized>(&self, w: &mut W) -> Result {
+        w.write_str(if self.compact { ":" } else { ": " })
+    }
+
+    // Fails up front under `NonFinite::Error`, so nothing is written for a bad value
+    pub(crate) fn check_finite(&self, value: &JSON) -> std::result::Result<(), JSONWriterError> {
+        if self.non_finite == NonFinite::Error && !self.json5 {
+            check_finite(value, false)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_str<W: Write + ?Sized>(&self, w: &mut W, s: &str) -> Result {
+        if !self.json5 {
+            return escape::write_escaped(w, s, self.ascii_only);
+        }
+        let doubles = s.matches('"').count();
+        let singles = s.matches('\'').count();
+        let quote = if doubles > singles { '\'' } else { '"' };
+        escape::write_quoted(w, s, quote, self.ascii_only)
+    }
+
+    pub(crate) fn write_key<W: Write + ?Sized>(&self, w: &mut W, key: &str) -> Result {
+        if self.json5 && is_identifier(key) {
+            w.write_str(key)
+        } else {
+            self.write_str(w, key)
+        }
+    }
+}
+
+pub(crate) fn write_value<W: Write + ?Sized>(
+    w: &mut W,
+    value: &JSON,
+    config: &PrettyConfig,
+    depth: usize,
+) -> Result {
+    match value {
+        JSON::Null => w.write_str("null"),
+        JSON::Bool(b) => write!(w, "{}", b),
+        JSON::Number(n) if !n.is_finite() && !config.json5 => write_non_finite(w, n, config),
+        JSON::Number(n) => write!(w, "{}", n),
+        JSON::String(s) => config.write_str(w, s),
+        JSON::Array(a) if a.is_empty() => w.write_str("[]"),
+        JSON::Array(a) => {
+            w.write_char('[')?;
+            config.write_newline(w, depth + 1)?;
+            for (i, v) in a.iter().enumerate() {
+                if i != 0 {
+                    config.write_comma(w, depth + 1)?;
+                }
+                write_value(w, v, config, depth + 1)?;
+            }
+            config.write_newline(w, depth)?;
+            w.write_char(']')
+        }
+        JSON::Object(o) if o.is_empty() => w.write_str("{}"),
+        JSON::Object(o) => {
+            w.write_char('{')?;
+            config.write_newline(w, depth + 1)?;
+            if config.sort_keys {
+                let mut members: Vec<(&String, &JSON)> = o.iter().collect();
+                members.sort_by_key(|(k, _)| *k);
+                write_members(w, members.into_iter(), config, depth + 1)?;
+            } else {
+                write_members(w, o.iter(), config, depth + 1)?;
+            }
+            config.write_newline(w, depth)?;
+            w.write_char('}')
+        }
+    }
+}
+
+fn write_non_finite<W: Write + ?Sized>(w: &mut W, n: &Number, config: &PrettyConfig) -> Result {
+    match config.non_finite {
+        NonFinite::Error => Err(std::fmt::Error),
+        NonFinite::Null => w.write_str("null"),
+        NonFinite::String => write!(w, "\"{}\"", n),
+    }
+}
+
+// Keys written bare in JSON5. Identifiers may also hold non-ASCII letters and
+// escapes, but those are left quoted.
+fn is_identifier(key: &str) -> bool {
+    let mut bytes = key.bytes();
+    matches!(bytes.next(), Some(b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'$'))
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$')
+}
+
+// Fails if `value` holds NaN or an infinity. With `as_f64` numbers are judged
+// by their f64 value, for output that converts them: `arbitrary_precision`
+// text like `1e400` is then infinite, where otherwise it is written as is.
+pub(crate) fn check_finite(value: &JSON, as_f64: bool) -> std::result::Result<(), JSONWriterError> {
+    let finite = match value {
+        JSON::Number(n) if as_f64 => n.as_f64().is_some_and(f64::is_finite),
+        JSON::Number(n) => n.is_finite(),
+        JSON::Array(a) => return a.iter().try_for_each(|v| check_finite(v, as_f64)),
+        JSON::Object(o) => return o.values().try_for_each(|v| check_finite(v, as_f64)),
+        _ => true,
+    };
+    if finite {
+        Ok(())
+    } else {
+        Err(JSONWriterError::NonFiniteNumber)
+    }
+}
+
+fn write_members<'a, W: Write + ?Sized>(
+    w: &mut W,
+    members: impl Iterator<Item = (&'a String, &'a JSON)>,
+    config: &PrettyConfig,
+    depth: usize,
+) -> Result {
+    for (i, (k, v)) in members.enumerate() {
+        if i != 0 {
+            config.write_comma(w, depth)?;
+        }
+        config.write_key(w, k)?;
+        config.write_colon(w)?;
+        write_value(w, v, config, depth)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,302 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+use super::{pretty, Number, PrettyConfig, JSON};
+
+#[derive(Debug)]
+pub enum JSONWriterError {
+    Io(io::Error),
+    InvalidState(&'static str),
+    NonFiniteNumber,
+}
+
+impl Display for JSONWriterError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            JSONWriterError::Io(e) => write!(f, "IO error: {}", e),
+            JSONWriterError::InvalidState(s) => write!(f, "Invalid writer state: {}", s),
+            JSONWriterError::NonFiniteNumber => {
+                write!(f, "NaN and infinite numbers cannot be written as JSON")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for JSONWriterError {
+    fn from(value: io::Error) -> Self {
+        JSONWriterError::Io(value)
+    }
+}
+
+// Lets the `fmt::Write` based serializer write to an `io::Write`,
+// holding on to the underlying IO error since `fmt::Error` carries none.
+pub(crate) struct IoAdapter<W: io::Write> {
+    pub(crate) inner: W,
+    pub(crate) error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoAdapter<W> {
+    pub(crate) fn new(inner: W) -> IoAdapter<W> {
+        IoAdapter { inner, error: None }
+    }
+
+    pub(crate) fn take_error(&mut self, result: fmt::Result) -> io::Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self
+                .error
+                .take()
+                .unwrap_or_else(|| io::Error::other("Formatter error"))),
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+impl JSON {
+    // Writes the same text as `to_string`; wrap unbuffered writers in a `BufWriter`
+    pub fn write_to<W: io::Write>(&self, w: W) -> io::Result<()> {
+        self.write_pretty_to(w, &PrettyConfig::inline())
+    }
+
+    // Fails with `InvalidData` if `config.non_finite` is `NonFinite::Error` and
+    // the value holds NaN or an infinity
+    pub fn write_pretty_to<W: io::Write>(&self, w: W, config: &PrettyConfig) -> io::Result<()> {
+        config
+            .check_finite(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut adapter = IoAdapter::new(w);
+        let result = pretty::write_value(&mut adapter, self, config, 0);
+        adapter.take_error(result)
+    }
+}
+
+enum Scope {
+    Array { empty: bool },
+    Object { empty: bool, has_key: bool },
+}
+
+// Writes a single JSON value piece by piece, so large documents can be produced
+// without building a `JSON` tree. Calls that would produce malformed output,
+// like a value inside an object without a key, return `InvalidState`.
+pub struct JSONWriter<W: io::Write> {
+    out: IoAdapter<W>,
+    config: PrettyConfig,
+    stack: Vec<Scope>,
+    done: bool,
+}
+
+impl<W: io::Write> JSONWriter<W> {
+    pub fn new(w: W) -> JSONWriter<W> {
+        JSONWriter::with_config(w, PrettyConfig::compact())
+    }
+
+    // `sort_keys` has no effect since keys are written in the order given
+    pub fn with_config(w: W, config: PrettyConfig) -> JSONWriter<W> {
+        JSONWriter {
+            out: IoAdapter::new(w),
+            config,
+            stack: Vec::new(),
+            done: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), JSONWriterError> {
+        self.before_value()?;
+        self.write(|out, _| fmt::Write::write_char(out, '{'))?;
+        self.stack.push(Scope::Object {
+            empty: true,
+            has_key: false,
+        });
+        Ok(())
+    }
+
+    pub fn key(&mut self, key: &str) -> Result<(), JSONWriterError> {
+        let depth = self.stack.len();
+        let first = match self.stack.last_mut() {
+            Some(Scope::Object { has_key: true, .. }) => {
+                return Err(JSONWriterError::InvalidState(
+                    "Key written twice without a value",
+                ))
+            }
+            Some(Scope::Object { empty, has_key }) => {
+                *has_key = true;
+                std::mem::replace(empty, false)
+            }
+            _ => {
+                return Err(JSONWriterError::InvalidState(
+                    "Key written outside of an object",
+                ))
+            }
+        };
+        self.write(|out, config| {
+            if first {
+                config.write_newline(out, depth)?;
+            } else {
+                config.write_comma(out, depth)?;
+            }
+            config.write_key(out, key)?;
+            config.write_colon(out)
+        })
+    }
+
+    pub fn end_object(&mut self) -> Result<(), JSONWriterError> {
+        let empty = match self.stack.last() {
+            Some(Scope::Object { has_key: true, .. }) => {
+                return Err(JSONWriterError::InvalidState(
+                    "Object ended after a key with no value",
+                ))
+            }
+            Some(Scope::Object { empty, .. }) => *empty,
+            _ => return Err(JSONWriterError::InvalidState("Unexpected end of object")),
+        };
+        self.stack.pop();
+        self.end_container(empty, '}')
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), JSONWriterError> {
+        self.before_value()?;
+        self.write(|out, _| fmt::Write::write_char(out, '['))?;
+        self.stack.push(Scope::Array { empty: true });
+        Ok(())
+    }
+
+    pub fn end_array(&mut self) -> Result<(), JSONWriterError> {
+        let empty = match self.stack.last() {
+            Some(Scope::Array { empty }) => *empty,
+            _ => return Err(JSONWriterError::InvalidState("Unexpected end of array")),
+        };
+        self.stack.pop();
+        self.end_container(empty, ']')
+    }
+
+    pub fn value(&mut self, value: &JSON) -> Result<(), JSONWriterError> {
+        self.config.check_finite(value)?;
+        self.before_value()?;
+        let depth = self.stack.len();
+        self.write(|out, config| pretty::write_value(out, value, config, depth))?;
+        self.after_value();
+        Ok(())
+    }
+
+    pub fn string(&mut self, s: &str) -> Result<(), JSONWriterError> {
+        self.before_value()?;
+        self.write(|out, config| config.write_str(out, s))?;
+        self.after_value();
+        Ok(())
+    }
+
+    pub fn number<N: Into<Number>>(&mut self, n: N) -> Result<(), JSONWriterError> {
+        self.value(&JSON::Number(n.into()))
+    }
+
+    pub fn bool(&mut self, b: bool) -> Result<(), JSONWriterError> {
+        self.value(&JSON::Bool(b))
+    }
+
+    pub fn null(&mut self) -> Result<(), JSONWriterError> {
+        self.value(&JSON::Null)
+    }
+
+    // Checks that exactly one complete value was written and returns the writer
+    pub fn finish(mut self) -> Result<W, JSONWriterError> {
+        if !self.stack.is_empty() {
+            return Err(JSONWriterError::InvalidState("Unclosed array or object"));
+        }
+        if !self.done {
+            return Err(JSONWriterError::InvalidState("No value written"));
+        }
+        self.out.inner.flush()?;
+        Ok(self.out.inner)
+    }
+
+    fn before_value(&mut self) -> Result<(), JSONWriterError> {
+        let depth = self.stack.len();
+        match self.stack.last_mut() {
+            None if self.done => Err(JSONWriterError::InvalidState(
+                "Only one top-level value can be written",
+            )),
+            None => Ok(()),
+            Some(Scope::Object { has_key: false, .. }) => Err(JSONWriterError::InvalidState(
+                "Value written inside an object without a key",
+            )),
+            Some(Scope::Object { has_key, .. }) => {
+                *has_key = false;
+                Ok(())
+            }
+            Some(Scope::Array { empty }) => {
+                let first = std::mem::replace(empty, false);
+                self.write(|out, config| {
+                    if first {
+                        config.write_newline(out, depth)
+                    } else {
+                        config.write_comma(out, depth)
+                    }
+                })
+            }
+        }
+    }
+
+    fn after_value(&mut self) {
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+    }
+
+    fn end_container(&mut self, empty: bool, close: char) -> Result<(), JSONWriterError> {
+        let depth = self.stack.len();
+        self.write(|out, config| {
+            if !empty {
+                config.write_newline(out, depth)?;
+            }
+            fmt::Write::write_char(out, close)
+        })?;
+        self.after_value();
+        Ok(())
+    }
+
+    fn write<F>(&mut self, f: F) -> Result<(), JSONWriterError>
+    where
+        F: FnOnce(&mut IoAdapter<W>, &PrettyConfig) -> fmt::Result,
+    {
+        let result = f(&mut self.out, &self.config);
+        Ok(self.out.take_error(result)?)
+    }
+}
+
+// Writes newline-delimited JSON: each value compactly on its own line
+pub struct NDJSONWriter<W: io::Write> {
+    out: IoAdapter<W>,
+}
+
+impl<W: io::Write> NDJSONWriter<W> {
+    pub fn new(w: W) -> NDJSONWriter<W> {
+        NDJSONWriter {
+            out: IoAdapter::new(w),
+        }
+    }
+
+    pub fn write(&mut self, value: &JSON) -> io::Result<()> {
+        let result = pretty::write_value(&mut self.out, value, &PrettyConfig::compact(), 0)
+            .and_then(|()| fmt::Write::write_char(&mut self.out, '\n'));
+        self.out.take_error(result)
+    }
+
+    pub fn write_all<'a, I: IntoIterator<Item = &'a JSON>>(&mut self, values: I) -> io::Result<()> {
+        values.into_iter().try_for_each(|v| self.write(v))
+    }
+
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.out.inner.flush()?;
+        Ok(self.out.inner)
+    }
+}
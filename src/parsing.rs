@@ -1,72 +1,222 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc, str::FromStr};
+use std::{io::Read, str::FromStr};
+
+use crate::json::{Map, Number, JSON};
+use duplicates::DuplicateCheck;
+use limits::Limiter;
+
+mod duplicates;
+mod error;
+pub mod events;
+mod json5;
+mod limits;
+pub mod ndjson;
+pub(crate) mod numbers;
+mod reader;
+mod recover;
+pub mod stream;
+pub(crate) mod strings;
+
+pub use duplicates::DuplicateKeys;
+pub use error::{ErrorKind, JSONError};
+pub use events::{Comment, Event, EventReader, Position};
+pub use limits::ParseOptions;
+pub use ndjson::{NDJSONIter, NDJSONReader};
+pub use stream::StreamDeserializer;
+
+// How deeply arrays and objects may nest unless `ParseOptions` says otherwise.
+// Dropping, cloning, comparing and writing a `JSON` value all recurse, so
+// much deeper input would overflow the stack once parsed.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+// The extensions to RFC 8259 a parser accepts. JSONC, as used by VS Code
+// settings, only adds comments and trailing commas; JSON5 also adds identifier
+// keys, single quoted strings and more number forms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Dialect {
+    Json,
+    Jsonc,
+    Json5,
+}
 
-use crate::json::JSON;
+impl Dialect {
+    fn comments(self) -> bool {
+        self != Dialect::Json
+    }
 
-#[derive(Debug)]
-pub enum JSONError {
-    UnexpectedCharacter(char, usize, usize),
-    UnexpectedEndOfInput,
-    ParseError(&'static str),
+    fn trailing_commas(self) -> bool {
+        self != Dialect::Json
+    }
 }
 
-impl Display for JSONError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            JSONError::UnexpectedCharacter(c, l, p) => {
-                write!(
-                    f,
-                    "Unexpected character '{}' at line {} position {}",
-                    c, l, p
-                )
-            }
-            JSONError::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
-            JSONError::ParseError(s) => write!(f, "Parse error: {}", s),
-        }
-    }
+#[derive(Clone, Copy, PartialEq)]
+enum CommentKind {
+    Line,
+    Block,
 }
 
-struct TokenIterator<'a> {
-    s: &'a str,
+// The byte level tokenizer shared by `TokenIterator` and the reader based
+// tokenizer. It scans `bytes` from `pos` and can stop at the end of a partial
+// buffer, resuming mid-token once more input has been appended. Comments are
+// returned as tokens of their own, starting with `//` or `/*`.
+pub(crate) struct Scanner {
     line: usize,
     char: usize,
     pos: usize,
+    start: usize,
+    start_line: usize,
+    start_char: usize,
+    start_offset: usize,
+    // Byte offset of the start of the buffer, which moves when it is shifted
+    base: usize,
 
     escaped: bool,
     in_string: bool,
+    quote: u8,
+    comment: Option<CommentKind>,
+    star: bool,
+    dialect: Dialect,
 }
 
-impl<'a> TokenIterator<'a> {
-    fn new(s: &'a str) -> TokenIterator<'a> {
-        TokenIterator {
-            s,
-            line: 1,
-            char: 1,
+pub(crate) enum Scan {
+    // A token spanning `start..scanner.pos`
+    Token(usize),
+    Error(JSONError),
+    // No more tokens; only returned once the end of input is known
+    End,
+    // Hit the end of the buffer, bytes from `scanner.start` on are still needed
+    NeedMore,
+}
+
+impl Scanner {
+    pub(crate) fn new() -> Scanner {
+        Scanner::starting_at(Position::new(1, 1, 0))
+    }
+
+    // For input that is a slice of a larger document starting at `at`
+    pub(crate) fn starting_at(at: Position) -> Scanner {
+        Scanner {
+            line: at.line,
+            char: at.column,
             pos: 0,
+            start: 0,
+            start_line: at.line,
+            start_char: at.column,
+            start_offset: at.offset,
+            base: at.offset,
             escaped: false,
             in_string: false,
+            quote: b'"',
+            comment: None,
+            star: false,
+            dialect: Dialect::Json,
         }
     }
 
-    fn get_line(&self) -> usize {
-        self.line
+    pub(crate) fn with_dialect(mut self, dialect: Dialect) -> Scanner {
+        self.dialect = dialect;
+        self
     }
 
-    fn get_char(&self) -> usize {
-        self.char
+    // Position of the first character of the most recently returned token
+    pub(crate) fn position(&self) -> Position {
+        Position::new(self.start_line, self.start_char, self.start_offset)
     }
-}
 
-const WHITESPACE: [u8; 4] = [b'\x20', b'\x09', b'\x0a', b'\x09'];
-const CONTROL_CHARS: [u8; 6] = [b'{', b'}', b'[', b']', b':', b','];
+    // Position of the next unscanned byte, the end of input once scanning is done
+    pub(crate) fn here(&self) -> Position {
+        Position::new(self.line, self.char, self.base + self.pos)
+    }
 
-impl<'a> Iterator for TokenIterator<'a> {
-    type Item = Result<&'a str, JSONError>;
+    // Called after the bytes before `start` have been dropped from the buffer
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    // Position of the first character of the token being scanned
+    pub(crate) fn token_start(&self) -> Position {
+        Position::new(self.start_line, self.start_char, self.base + self.start)
+    }
+
+    pub(crate) fn shift(&mut self, by: usize) {
+        self.pos -= by;
+        self.start -= by;
+        self.base += by;
+    }
+
+    // Steps over the byte an error was reported at, so scanning can go on
+    pub(crate) fn skip_byte(&mut self) {
+        self.pos += 1;
+        self.char += 1;
+        self.start = self.pos;
+    }
+
+    fn token(&mut self) -> Scan {
+        let start = self.start;
+        self.start_offset = self.base + start;
+        self.start = self.pos;
+        Scan::Token(start)
+    }
+
+    pub(crate) fn scan(&mut self, bytes: &[u8], eof: bool) -> Scan {
+        while let Some(&char) = bytes.get(self.pos) {
+            if self.comment.is_some() {
+                match self.scan_comment(char) {
+                    Some(scan) => return scan,
+                    None => continue,
+                }
+            }
+
+            if !self.in_string && self.dialect != Dialect::Json {
+                // Whitespace beyond RFC 8259's four characters, some of it multi-byte
+                if self.dialect == Dialect::Json5 && (char == 0x0B || char == 0x0C || char >= 0x80)
+                {
+                    if char >= 0x80 && bytes.len() - self.pos < 3 && !eof {
+                        return Scan::NeedMore;
+                    }
+                    let len = json5_whitespace(&bytes[self.pos..]);
+                    if len > 0 {
+                        if self.start < self.pos {
+                            return self.token();
+                        }
+                        self.pos += len;
+                        self.char += len;
+                        self.start = self.pos;
+                        continue;
+                    }
+                }
+                if char == b'/' && self.dialect.comments() {
+                    if self.start < self.pos {
+                        return self.token();
+                    }
+                    let kind = match bytes.get(self.pos + 1) {
+                        Some(b'/') => CommentKind::Line,
+                        Some(b'*') => CommentKind::Block,
+                        None if !eof => return Scan::NeedMore,
+                        _ => return Scan::Error(JSONError::UnexpectedCharacter('/', self.here())),
+                    };
+                    self.start_line = self.line;
+                    self.start_char = self.char;
+                    self.comment = Some(kind);
+                    self.star = false;
+                    self.pos += 2;
+                    self.char += 2;
+                    continue;
+                }
+            }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut start_point = self.pos;
-        for char in self.s[self.pos..].bytes() {
             let old_line = self.line;
             let old_char = self.char;
+
+            // A bare literal ends at the next whitespace, quote or control character,
+            // which is left unconsumed for the following call.
+            if !self.in_string && self.start < self.pos && self.ends_literal(char) {
+                return self.token();
+            }
+
             if char == b'\n' {
                 self.line += 1;
                 self.char = 1;
@@ -81,17 +231,24 @@ impl<'a> Iterator for TokenIterator<'a> {
 
             if !self.in_string && WHITESPACE.contains(&char) {
                 self.pos += 1;
-                start_point = self.pos;
+                self.start = self.pos;
                 continue;
             }
 
-            if char == b'\\' {
+            if !self.in_string && self.start == self.pos {
+                self.start_line = old_line;
+                self.start_char = old_char;
+            }
+
+            // JSON5 identifiers may contain `\u` escapes
+            if char == b'\\' && (self.in_string || self.dialect != Dialect::Json5) {
                 if !self.in_string {
-                    return Some(Err(JSONError::UnexpectedCharacter(
+                    self.line = old_line;
+                    self.char = old_char;
+                    return Scan::Error(JSONError::UnexpectedCharacter(
                         char as char,
-                        old_line,
-                        old_char,
-                    )));
+                        Position::new(old_line, old_char, self.base + self.pos),
+                    ));
                 }
                 if escaped {
                     self.escaped = false;
@@ -102,16 +259,19 @@ impl<'a> Iterator for TokenIterator<'a> {
                 }
             }
 
-            if char == b'"' && !escaped {
-                if !self.in_string {
-                    self.in_string = true;
-                    start_point = self.pos;
+            if !escaped {
+                if self.in_string && char == self.quote {
                     self.pos += 1;
-                    continue;
-                } else {
                     self.in_string = false;
+                    return self.token();
+                }
+                if !self.in_string
+                    && (char == b'"' || (char == b'\'' && self.dialect == Dialect::Json5))
+                {
                     self.pos += 1;
-                    return Some(Ok(&self.s[start_point..self.pos]));
+                    self.in_string = true;
+                    self.quote = char;
+                    continue;
                 }
             }
 
@@ -120,360 +280,554 @@ impl<'a> Iterator for TokenIterator<'a> {
                 continue;
             }
 
-            if !escaped && CONTROL_CHARS.contains(&char) {
-                if start_point < self.pos {
-                    return Some(Ok(&self.s[start_point..self.pos]));
-                }
-                self.pos += 1;
-                return Some(Ok(&self.s[start_point..self.pos]));
-            }
             self.pos += 1;
+            if CONTROL_CHARS.contains(&char) {
+                return self.token();
+            }
+        }
+
+        if !eof {
+            return Scan::NeedMore;
+        }
+
+        if self.in_string || self.comment == Some(CommentKind::Block) {
+            return Scan::Error(JSONError::UnexpectedEndOfInput(self.here()));
         }
+        self.comment = None;
 
-        if self.in_string {
-            return Some(Err(JSONError::UnexpectedEndOfInput));
+        if self.start < self.pos {
+            return self.token();
         }
 
+        Scan::End
+    }
+
+    #[inline]
+    fn ends_literal(&self, char: u8) -> bool {
+        WHITESPACE.contains(&char)
+            || char == b'"'
+            || CONTROL_CHARS.contains(&char)
+            || (self.dialect == Dialect::Json5 && char == b'\'')
+    }
+
+    // Consumes one byte of a comment, returning the comment token once it ends.
+    // A line comment ends before its line break.
+    fn scan_comment(&mut self, char: u8) -> Option<Scan> {
+        match self.comment {
+            Some(CommentKind::Line) if char == b'\n' || char == b'\r' => {
+                self.comment = None;
+                return Some(self.token());
+            }
+            Some(CommentKind::Block) if self.star && char == b'/' => {
+                self.pos += 1;
+                self.char += 1;
+                self.comment = None;
+                return Some(self.token());
+            }
+            _ => (),
+        }
+        self.star = char == b'*';
+        if char == b'\n' {
+            self.line += 1;
+            self.char = 1;
+        } else {
+            self.char += 1;
+        }
+        self.pos += 1;
         None
     }
 }
 
-#[derive(Debug)]
-enum NodeMetadata<'a> {
-    Object(Vec<&'a str>),
-    Array,
-    Literal,
-    Default,
+// Length of the JSON5 whitespace character `bytes` starts with, or 0. Beyond
+// RFC 8259's whitespace JSON5 allows vertical tab, form feed, the line and
+// paragraph separators, the byte order mark and Unicode space separators.
+fn json5_whitespace(bytes: &[u8]) -> usize {
+    match bytes {
+        [0x0B | 0x0C, ..] => 1,
+        [0xC2, 0xA0, ..] => 2,
+        [0xE1, 0x9A, 0x80, ..]
+        | [0xE2, 0x80, 0x80..=0x8A | 0xA8 | 0xA9 | 0xAF, ..]
+        | [0xE2, 0x81, 0x9F, ..]
+        | [0xE3, 0x80, 0x80, ..]
+        | [0xEF, 0xBB, 0xBF, ..] => 3,
+        _ => 0,
+    }
 }
-#[derive(Debug)]
-struct Node<'a> {
-    children: Vec<Rc<RefCell<Node<'a>>>>,
-    metadata: NodeMetadata<'a>,
-    value: Option<JSON>,
+
+struct TokenIterator<'a> {
+    s: &'a str,
+    scanner: Scanner,
 }
 
-impl<'a> Node<'a> {
-    fn get_children(&self) -> &Vec<Rc<RefCell<Node<'a>>>> {
-        &self.children
+impl<'a> TokenIterator<'a> {
+    fn new(s: &'a str) -> TokenIterator<'a> {
+        TokenIterator {
+            s,
+            scanner: Scanner::new(),
+        }
     }
 
-    fn get_children_mut(&mut self) -> &mut Vec<Rc<RefCell<Node<'a>>>> {
-        &mut self.children
+    fn starting_at(s: &'a str, at: Position) -> TokenIterator<'a> {
+        TokenIterator {
+            s,
+            scanner: Scanner::starting_at(at),
+        }
     }
 
-    fn add_child_wrapped(&mut self, node: Rc<RefCell<Node<'a>>>) {
-        self.children.push(node)
+    fn with_dialect(s: &'a str, dialect: Dialect) -> TokenIterator<'a> {
+        TokenIterator {
+            s,
+            scanner: Scanner::new().with_dialect(dialect),
+        }
     }
 
-    fn new(metadata: NodeMetadata, value: Option<JSON>) -> Node {
-        Node {
-            children: Vec::new(),
-            metadata,
-            value,
-        }
+    // Byte offset just past the most recently returned token
+    fn pos(&self) -> usize {
+        self.scanner.pos
+    }
+
+    fn position(&self) -> Position {
+        self.scanner.position()
+    }
+
+    fn end(&self) -> Position {
+        self.scanner.here()
     }
 }
 
-impl<'a> Default for Node<'a> {
-    fn default() -> Node<'a> {
-        Node {
-            children: Vec::new(),
-            metadata: NodeMetadata::Default,
-            value: None,
+const WHITESPACE: [u8; 4] = [b'\x20', b'\x09', b'\x0a', b'\x0d'];
+const CONTROL_CHARS: [u8; 6] = [b'{', b'}', b'[', b']', b':', b','];
+
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Result<&'a str, JSONError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.scanner.scan(self.s.as_bytes(), true) {
+            Scan::Token(start) => Some(Ok(&self.s[start..self.scanner.pos])),
+            Scan::Error(e) => Some(Err(e)),
+            Scan::End | Scan::NeedMore => None,
         }
     }
 }
 
-fn add_to_top<'a>(
-    vect: &mut Vec<Rc<RefCell<Node<'a>>>>,
-    child_node: Rc<RefCell<Node<'a>>>,
-    err_str: &'static str,
-) -> Result<(), JSONError> {
-    let top_node = vect.last();
-    let cur_node = (*child_node).borrow();
-    match top_node {
-        Some(rc) => {
-            let mut top_node = (*rc).borrow_mut();
-            match (&cur_node.metadata, &top_node.metadata) {
-                (
-                    NodeMetadata::Object(_) | NodeMetadata::Array | NodeMetadata::Literal,
-                    NodeMetadata::Object(_) | NodeMetadata::Array | NodeMetadata::Default,
-                ) => {
-                    top_node.add_child_wrapped(child_node.clone());
-                    Ok(())
-                }
-                (_, _) => Err(JSONError::ParseError(err_str)),
-            }
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    Array,
+    Object,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    FirstValueOrEnd,
+    Key,
+    FirstKeyOrEnd,
+    Colon,
+    CommaOrEnd,
+    Done,
+}
+
+// Checks that tokens arrive in a valid order and turns each one into an
+// `Event`. Separators (`,` and `:`) produce no event.
+pub(crate) struct Grammar {
+    stack: Vec<Container>,
+    expect: Expect,
+    dialect: Dialect,
+    max_depth: Option<usize>,
+}
+
+impl Grammar {
+    pub(crate) fn new() -> Grammar {
+        Grammar::with_dialect(Dialect::Json)
+    }
+
+    pub(crate) fn with_dialect(dialect: Dialect) -> Grammar {
+        Grammar {
+            stack: Vec::new(),
+            expect: Expect::Value,
+            dialect,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
         }
-        None => Ok(()),
     }
-}
 
-//Complete and utter guess, don't want to compute exact number of commas in JSON object
-
-const BYTES_PER_OBJECT_APPROX: usize = 10;
-fn tree_from_tokens(s: &str) -> Result<Vec<Rc<RefCell<Node>>>, JSONError> {
-    let approx_tokens = (s.len() as f64 / BYTES_PER_OBJECT_APPROX as f64).ceil() as usize;
-    let tokens = TokenIterator::new(s);
-    let mut nodes = Vec::with_capacity(approx_tokens + 1);
-    let top_node = Node::new(NodeMetadata::Default, None);
-    let top_node_ref = Rc::new(RefCell::new(top_node));
-    let mut current_scope: Vec<Rc<RefCell<Node>>> = vec![top_node_ref.clone()];
-    let mut next_is_key = false;
-    drop(top_node_ref);
-    for token in tokens {
-        match token? {
-            "{" => {
-                let obj_node = Node::new(NodeMetadata::Object(Vec::new()), None);
-                let wrapped_obj_node = Rc::new(RefCell::new(obj_node));
-                add_to_top(
-                    &mut current_scope,
-                    wrapped_obj_node.clone(),
-                    "Unexpected start of object",
-                )?;
-                nodes.push(wrapped_obj_node.clone());
-                current_scope.push(wrapped_obj_node);
-                next_is_key = true;
-            }
-            ":" => {
-                let scope = current_scope.last();
-                if scope.is_none() {
-                    return Err(JSONError::ParseError("Unexpected colon"));
-                }
+    pub(crate) fn with_max_depth(mut self, max_depth: Option<usize>) -> Grammar {
+        self.max_depth = max_depth;
+        self
+    }
 
-                let scope = scope.unwrap();
-                let node = (*scope).borrow();
-                match node.metadata {
-                    NodeMetadata::Object(_) => (),
-                    _ => return Err(JSONError::ParseError("Unexpected colon")),
+    #[inline]
+    pub(crate) fn accept<'t>(
+        &mut self,
+        token: &'t str,
+        at: Position,
+    ) -> Result<Option<Event<'t>>, JSONError> {
+        let event = match token {
+            "{" | "[" => {
+                if !matches!(self.expect, Expect::Value | Expect::FirstValueOrEnd) {
+                    let kind = if token == "{" {
+                        ErrorKind::UnexpectedStartOfObject
+                    } else {
+                        ErrorKind::UnexpectedStartOfArray
+                    };
+                    return Err(self.unexpected(kind, at));
                 }
-                next_is_key = false;
-            }
-            "}" => {
-                let scope = current_scope.pop();
-                if scope.is_none() {
-                    return Err(JSONError::ParseError("Unexpected end curly brace"));
+                if let Some(max) = self.max_depth.filter(|&max| self.stack.len() >= max) {
+                    return Err(JSONError::DepthLimitExceeded(max, at));
                 }
-
-                let scope = scope.unwrap();
-                let node = (*scope).borrow();
-
-                match node.metadata {
-                    NodeMetadata::Object(_) => (),
-                    _ => return Err(JSONError::ParseError("Unexpected end curly brace")),
+                if token == "{" {
+                    self.stack.push(Container::Object);
+                    self.expect = Expect::FirstKeyOrEnd;
+                    Event::StartObject
+                } else {
+                    self.stack.push(Container::Array);
+                    self.expect = Expect::FirstValueOrEnd;
+                    Event::StartArray
                 }
             }
-            "[" => {
-                let arr_node = Node::new(NodeMetadata::Array, None);
-                let wrapped_arr_node = Rc::new(RefCell::new(arr_node));
-                add_to_top(
-                    &mut current_scope,
-                    wrapped_arr_node.clone(),
-                    "Unexpected start of array",
-                )?;
-                nodes.push(wrapped_arr_node.clone());
-                current_scope.push(wrapped_arr_node);
+            ":" => {
+                if self.expect != Expect::Colon {
+                    return Err(JSONError::ParseError(ErrorKind::UnexpectedColon, at));
+                }
+                self.expect = Expect::Value;
+                return Ok(None);
             }
             "," => {
-                let scope = current_scope.last();
-                match scope {
-                    Some(node_wr) => match (*node_wr).borrow().metadata {
-                        NodeMetadata::Array => (),
-                        NodeMetadata::Object(_) => {
-                            next_is_key = true;
-                        }
-                        _ => return Err(JSONError::ParseError("Unexpected comma")),
-                    },
-                    _ => return Err(JSONError::ParseError("Unexpected comma")),
+                self.expect = match (self.expect, self.stack.last()) {
+                    (Expect::CommaOrEnd, Some(Container::Array)) => Expect::Value,
+                    (Expect::CommaOrEnd, Some(Container::Object)) => Expect::Key,
+                    _ => return Err(JSONError::ParseError(ErrorKind::UnexpectedComma, at)),
                 };
+                return Ok(None);
             }
-            "]" => {
-                let scope = current_scope.pop();
-                match scope {
-                    None => return Err(JSONError::ParseError("Unexpected end square brace")),
-                    Some(rc) => match (*rc).borrow().metadata {
-                        NodeMetadata::Array => (),
-                        _ => return Err(JSONError::ParseError("Unexpected end square brace")),
-                    },
+            "}" => match (self.expect, self.stack.last()) {
+                (Expect::FirstKeyOrEnd | Expect::CommaOrEnd, Some(Container::Object)) => {
+                    self.stack.pop();
+                    self.value_done();
+                    Event::EndObject
                 }
-            }
-            st => {
-                let (json_val, error_str) = match st {
-                    "true" => (JSON::Bool(true), "Unexpected boolean literal"),
-                    "false" => (JSON::Bool(false), "Unexpected boolean literal"),
-                    "null" => (JSON::Null, "Unexpected null value"),
-                    _ => {
-                        if let Ok(num) = st.parse::<f64>() {
-                            (JSON::Number(num), "Unexpected number")
-                        } else {
-                            (
-                                JSON::String(st[1..st.len() - 1].to_string()),
-                                "Unexpected string",
-                            )
-                        }
-                    }
-                };
-
-                if next_is_key {
-                    let parent = current_scope.last();
-                    match parent {
-                        None => (),
-                        Some(rc) => {
-                            let mut node = (*rc).borrow_mut();
-                            match (&node.metadata, &json_val) {
-                                (NodeMetadata::Object(_), JSON::String(_)) => {
-                                    let md = &mut node.metadata;
-                                    if let NodeMetadata::Object(keys) = md {
-                                        keys.push(&st[1..st.len() - 1]);
-                                    }
-                                    continue;
-                                }
-                                (NodeMetadata::Object(_), _) => {
-                                    return Err(JSONError::ParseError(
-                                        "Non string used as object key",
-                                    ))
-                                }
-                                _ => {
-                                    return Err(JSONError::ParseError(
-                                        "Tried to add key to non-object",
-                                    ))
-                                }
-                            }
-                        }
+                (Expect::Key, Some(Container::Object)) if self.dialect.trailing_commas() => {
+                    self.stack.pop();
+                    self.value_done();
+                    Event::EndObject
+                }
+                (Expect::Key, Some(Container::Object)) => {
+                    return Err(JSONError::ParseError(ErrorKind::TrailingCommaInObject, at))
+                }
+                _ => return Err(JSONError::ParseError(ErrorKind::UnexpectedEndOfObject, at)),
+            },
+            "]" => match (self.expect, self.stack.last()) {
+                (Expect::FirstValueOrEnd | Expect::CommaOrEnd, Some(Container::Array)) => {
+                    self.stack.pop();
+                    self.value_done();
+                    Event::EndArray
+                }
+                (Expect::Value, Some(Container::Array)) if self.dialect.trailing_commas() => {
+                    self.stack.pop();
+                    self.value_done();
+                    Event::EndArray
+                }
+                (Expect::Value, Some(Container::Array)) => {
+                    return Err(JSONError::ParseError(ErrorKind::TrailingCommaInArray, at))
+                }
+                _ => return Err(JSONError::ParseError(ErrorKind::UnexpectedEndOfArray, at)),
+            },
+            st if self.dialect.comments() && st.starts_with('/') => return Ok(None),
+            st if self.dialect == Dialect::Json5 => return self.accept_json5(st, at),
+            st => match self.expect {
+                Expect::Key | Expect::FirstKeyOrEnd => {
+                    if !st.starts_with('"') {
+                        return Err(JSONError::ParseError(ErrorKind::NonStringKey, at));
                     }
+                    self.expect = Expect::Colon;
+                    Event::Key(strings::decode_string(&st[1..st.len() - 1], at.advance(1))?)
+                }
+                Expect::Value | Expect::FirstValueOrEnd => {
+                    let event = scalar_event(st, at)?;
+                    self.value_done();
+                    event
                 }
-                let node = Node::new(NodeMetadata::Literal, Some(json_val));
-                let wrapped_node = Rc::new(RefCell::new(node));
-                nodes.push(wrapped_node.clone());
-                add_to_top(&mut current_scope, wrapped_node, error_str)?;
+                _ => {
+                    // Still validate the token so malformed input reports its position
+                    let event = scalar_event(st, at)?;
+                    return Err(self.unexpected_scalar(&event, at));
+                }
+            },
+        };
+        Ok(Some(event))
+    }
+
+    // Keys and scalars in JSON5, which have their own string and number syntax
+    fn accept_json5<'t>(
+        &mut self,
+        st: &'t str,
+        at: Position,
+    ) -> Result<Option<Event<'t>>, JSONError> {
+        let event = match self.expect {
+            Expect::Key | Expect::FirstKeyOrEnd => {
+                self.expect = Expect::Colon;
+                Event::Key(json5::decode_key(st, at)?)
             }
+            Expect::Value | Expect::FirstValueOrEnd => {
+                let event = json5::scalar_event(st, at)?;
+                self.value_done();
+                event
+            }
+            _ => {
+                let event = json5::scalar_event(st, at)?;
+                return Err(self.unexpected_scalar(&event, at));
+            }
+        };
+        Ok(Some(event))
+    }
+
+    fn value_done(&mut self) {
+        self.expect = if self.stack.is_empty() {
+            Expect::Done
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+
+    fn unexpected(&self, kind: ErrorKind, at: Position) -> JSONError {
+        if self.expect == Expect::Done {
+            JSONError::ParseError(ErrorKind::MultipleValues, at)
+        } else {
+            JSONError::ParseError(kind, at)
         }
     }
 
-    if current_scope.len() > 1 {
-        return Err(JSONError::ParseError(
-            "More than one independent JSON object detected",
-        ));
+    fn unexpected_scalar(&self, event: &Event, at: Position) -> JSONError {
+        let kind = match event {
+            Event::Bool(_) => ErrorKind::UnexpectedBool,
+            Event::Null => ErrorKind::UnexpectedNull,
+            Event::String(_) => ErrorKind::UnexpectedString,
+            _ => ErrorKind::UnexpectedNumber,
+        };
+        self.unexpected(kind, at)
     }
-    let tree_head = current_scope.pop();
-    if tree_head.is_none() {
-        return Err(JSONError::ParseError(
-            "Extremely unexpected parsing error, everything consumed?",
-        ));
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.expect == Expect::Done
+    }
+
+    // `end` is the position of the end of input
+    pub(crate) fn finish(&self, end: Position) -> Result<(), JSONError> {
+        if self.is_done() {
+            Ok(())
+        } else {
+            Err(JSONError::UnexpectedEndOfInput(end))
+        }
     }
-    Ok(nodes)
 }
 
-fn consume_tree(mut node_order: Vec<Rc<RefCell<Node>>>) -> Result<JSON, JSONError> {
-    node_order.reverse();
-    let mut iter = node_order.drain(..);
-    let parsed_json = loop {
-        let node = iter
-            .next()
-            .expect("Should break at bottom, non child node is root");
-        let mut n = (*node).borrow_mut();
+fn scalar_event(st: &str, at: Position) -> Result<Event<'_>, JSONError> {
+    match st {
+        "true" => Ok(Event::Bool(true)),
+        "false" => Ok(Event::Bool(false)),
+        "null" => Ok(Event::Null),
+        _ if st.starts_with('"') => Ok(Event::String(strings::decode_string(
+            &st[1..st.len() - 1],
+            at.advance(1),
+        )?)),
+        _ => match numbers::scan_number(st) {
+            Ok(()) => Number::from_validated_str(st)
+                .map(Event::Number)
+                .ok_or(JSONError::ParseError(ErrorKind::NumberOutOfRange, at)),
+            Err(offset) => Err(JSONError::UnexpectedCharacter(
+                st[offset..].chars().next().expect("In bounds"),
+                at.advance(offset),
+            )),
+        },
+    }
+}
 
-        if n.value.is_some() {
-            continue;
-        }
+enum Frame {
+    Array(Vec<JSON>),
+    Object(Map, Option<String>),
+}
 
-        match &n.metadata {
-            NodeMetadata::Default => {
-                let children: &mut Vec<Rc<RefCell<Node<'_>>>> = n.get_children_mut();
-                if children.len() != 1 {
-                    return Err(JSONError::ParseError(
-                        "Keyed object has more than one child",
-                    ));
-                }
+// Builds a `JSON` value from a stream of already validated events in a single
+// pass. Open arrays and objects live on `stack`; a finished value is moved
+// straight into its parent.
+pub(crate) struct ValueBuilder {
+    stack: Vec<Frame>,
+    duplicates: DuplicateKeys,
+    // Keys turned into arrays by `DuplicateKeys::Collect`, with their depth
+    collected: Vec<(usize, String)>,
+}
+
+impl ValueBuilder {
+    pub(crate) fn new() -> ValueBuilder {
+        ValueBuilder::with_duplicates(DuplicateKeys::default())
+    }
 
-                let val_node_rc = children.pop().expect("Has 1 child");
-                let val_node = Rc::into_inner(val_node_rc)
-                    .expect("Should be only child")
-                    .into_inner();
-                n.value = val_node.value;
+    pub(crate) fn with_duplicates(duplicates: DuplicateKeys) -> ValueBuilder {
+        ValueBuilder {
+            stack: Vec::new(),
+            duplicates,
+            collected: Vec::new(),
+        }
+    }
+
+    // Returns the value once the outermost array, object or scalar is complete
+    #[inline]
+    pub(crate) fn push(&mut self, event: Event) -> Option<JSON> {
+        let value = match event {
+            Event::StartObject => {
+                self.stack.push(Frame::Object(Map::new(), None));
+                return None;
             }
-            NodeMetadata::Array => {
-                let children = n.get_children_mut();
-                let mut json_vs = Vec::with_capacity(children.len());
-                let mut err = false;
-
-                children.drain(..).for_each(|child| {
-                    let child_node = Rc::into_inner(child)
-                        .expect("Should be only child now")
-                        .into_inner();
-                    if let Some(js) = child_node.value {
-                        json_vs.push(js);
-                    } else {
-                        err = true;
-                    }
-                });
-                if err {
-                    return Err(JSONError::ParseError("Unparsed child of array object"));
+            Event::StartArray => {
+                self.stack.push(Frame::Array(Vec::new()));
+                return None;
+            }
+            Event::Key(key) => {
+                if let Some(Frame::Object(_, pending)) = self.stack.last_mut() {
+                    *pending = Some(key.into_owned());
                 }
-                n.value = Some(JSON::Array(json_vs))
+                return None;
             }
-            NodeMetadata::Object(keys) => {
-                let immut_children = n.get_children();
-                if immut_children.len() != keys.len() {
-                    return Err(JSONError::ParseError("Unkeyed child of object"));
+            Event::EndObject | Event::EndArray => {
+                let depth = self.stack.len();
+                if !self.collected.is_empty() {
+                    self.collected.retain(|(d, _)| *d < depth);
                 }
-                let mut json_ob = HashMap::with_capacity(immut_children.len());
-
-                let mut key_strs: Vec<String> = keys.iter().map(|s| String::from(*s)).collect();
-                let children = n.get_children_mut();
-                let mut err = false;
-                let mut err_str = "";
-                let drain_iter = children.drain(..);
-                let zipped_iter = drain_iter.zip(key_strs.drain(..));
-                zipped_iter.for_each(|(child, key)| {
-                    let child_node = Rc::into_inner(child)
-                        .expect("Should be only child")
-                        .into_inner();
-                    if child_node.value.is_none() {
-                        err = true;
-                        err_str = "Unparsed child of object";
-                    }
-                    let child_val = child_node.value.unwrap();
-                    json_ob.insert(key, child_val);
-                });
-
-                if err {
-                    return Err(JSONError::ParseError(err_str));
+                match self.stack.pop() {
+                    Some(Frame::Object(map, _)) => JSON::Object(map),
+                    Some(Frame::Array(arr)) => JSON::Array(arr),
+                    None => panic!("Container end without a start"),
                 }
-
-                n.value = Some(JSON::Object(json_ob))
             }
-            NodeMetadata::Literal => continue,
-        }
-
-        if Rc::strong_count(&node) == 1 {
-            drop(n);
-            let inner_node = Rc::into_inner(node)
-                .expect("Guaranteed to be zero")
-                .into_inner();
-            break inner_node.value;
-        }
-    };
-    match iter.next() {
-        None => (),
-        Some(_) => {
-            return Err(JSONError::ParseError(
-                "Multiple independent JSON objects present",
-            ))
+            Event::String(s) => JSON::String(s.into_owned()),
+            Event::Number(n) => JSON::Number(n),
+            Event::Bool(b) => JSON::Bool(b),
+            Event::Null => JSON::Null,
+        };
+
+        let depth = self.stack.len();
+        match self.stack.last_mut() {
+            None => Some(value),
+            Some(Frame::Array(arr)) => {
+                arr.push(value);
+                None
+            }
+            Some(Frame::Object(map, pending)) => {
+                let key = pending.take().expect("Key is read before its value");
+                if self.duplicates == DuplicateKeys::LastWins {
+                    map.insert(key, value);
+                } else {
+                    self.duplicates
+                        .insert(map, key, value, depth, &mut self.collected);
+                }
+                None
+            }
         }
     }
-    if let Some(js) = parsed_json {
-        Ok(js)
-    } else {
-        Err(JSONError::ParseError("Parsing failed??"))
-    }
 }
 
 impl FromStr for JSON {
     type Err = JSONError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let nodes = tree_from_tokens(s)?;
-        consume_tree(nodes)
+        parse_str(s, Dialect::Json, None, &ParseOptions::default())
+    }
+}
+
+// Comment tokens are added to `comments` if given, otherwise dropped
+fn parse_str<'a>(
+    s: &'a str,
+    dialect: Dialect,
+    mut comments: Option<&mut Vec<Comment<'a>>>,
+    options: &ParseOptions,
+) -> Result<JSON, JSONError> {
+    options.check_input(s)?;
+    let mut limiter = Limiter::new(options);
+    let mut duplicates = DuplicateCheck::new(options.duplicate_keys);
+    let mut tokens = TokenIterator::with_dialect(s, dialect);
+    let mut grammar = Grammar::with_dialect(dialect).with_max_depth(options.max_depth);
+    let mut builder = ValueBuilder::with_duplicates(options.duplicate_keys);
+    let mut result = None;
+    while let Some(token) = tokens.next() {
+        let token = token?;
+        if let Some(comments) = comments.as_deref_mut().filter(|_| token.starts_with('/')) {
+            comments.push(Comment::new(token, tokens.position()));
+            continue;
+        }
+        if let Some(event) = grammar.accept(token, tokens.position())? {
+            if let Some(limiter) = limiter.as_mut() {
+                limiter.check(&event, token, tokens.position())?;
+            }
+            if let Some(duplicates) = duplicates.as_mut() {
+                duplicates.check(&event, tokens.position())?;
+            }
+            result = result.or(builder.push(event));
+        }
+    }
+    grammar.finish(tokens.end())?;
+    Ok(result.expect("Grammar is done once a value is complete"))
+}
+
+impl JSON {
+    // Parses JSON5 (https://spec.json5.org): JSON plus comments, trailing commas,
+    // identifier keys, single quoted strings, hexadecimal numbers, leading and
+    // trailing decimal points, `+` signs, `Infinity`, `NaN` and line continuations
+    pub fn from_json5(s: &str) -> Result<JSON, JSONError> {
+        parse_str(s, Dialect::Json5, None, &ParseOptions::default())
+    }
+
+    // Parses JSON with `//` and `/* */` comments and trailing commas, the format
+    // of VS Code settings and tsconfig files. Comments are skipped.
+    pub fn from_jsonc(s: &str) -> Result<JSON, JSONError> {
+        parse_str(s, Dialect::Jsonc, None, &ParseOptions::default())
+    }
+
+    // Like `from_jsonc`, but also returns the comments in document order
+    pub fn from_jsonc_with_comments(s: &str) -> Result<(JSON, Vec<Comment<'_>>), JSONError> {
+        let mut comments = Vec::new();
+        let value = parse_str(
+            s,
+            Dialect::Jsonc,
+            Some(&mut comments),
+            &ParseOptions::default(),
+        )?;
+        Ok((value, comments))
+    }
+
+    // Like `from_str`, but failing once `options` limits are exceeded and
+    // handling repeated keys as `options.duplicate_keys` says. Use it for input
+    // from untrusted sources.
+    pub fn from_str_with(s: &str, options: &ParseOptions) -> Result<JSON, JSONError> {
+        parse_str(s, Dialect::Json, None, options)
+    }
+
+    // Parses a single JSON value from `reader` without reading it into memory first
+    pub fn from_reader<R: Read>(reader: R) -> Result<JSON, JSONError> {
+        JSON::from_reader_with(reader, &ParseOptions::default())
+    }
+
+    // Like `from_reader`, but with `options` as for `from_str_with`. Reading
+    // stops as soon as `max_input_bytes` is exceeded, or a string or number
+    // being read is certain to go over its limit.
+    pub fn from_reader_with<R: Read>(reader: R, options: &ParseOptions) -> Result<JSON, JSONError> {
+        let mut tokens = reader::ReadTokens::new(reader).with_limits(options);
+        let mut limiter = Limiter::new(options);
+        let mut duplicates = DuplicateCheck::new(options.duplicate_keys);
+        let mut grammar = Grammar::new().with_max_depth(options.max_depth);
+        let mut builder = ValueBuilder::with_duplicates(options.duplicate_keys);
+        let mut result = None;
+        while let Some(token) = tokens.next_token() {
+            let (token, at) = token?;
+            if let Some(event) = grammar.accept(token, at)? {
+                if let Some(limiter) = limiter.as_mut() {
+                    limiter.check(&event, token, at)?;
+                }
+                if let Some(duplicates) = duplicates.as_mut() {
+                    duplicates.check(&event, at)?;
+                }
+                result = result.or(builder.push(event));
+            }
+        }
+        grammar.finish(tokens.end())?;
+        Ok(result.expect("Grammar is done once a value is complete"))
     }
 }
@@ -0,0 +1,100 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use super::{Event, JSONError, Position};
+use crate::json::{Map, JSON};
+
+// What parsing does with a key that appears more than once in an object
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    // Fail with `JSONError::DuplicateKey`, which has both positions
+    Error,
+    // Keep the first value and ignore the rest
+    FirstWins,
+    // Keep the last value, where the key first appeared
+    #[default]
+    LastWins,
+    // Keep every value, in order, in an array under the key. Keys that appear
+    // once are left as they are.
+    Collect,
+}
+
+impl DuplicateKeys {
+    // Adds a member to `map`, the object `depth` containers deep. `collected`
+    // holds the keys, with the depth of their object, whose value is already an
+    // array of duplicates.
+    pub(crate) fn insert(
+        self,
+        map: &mut Map,
+        key: String,
+        value: JSON,
+        depth: usize,
+        collected: &mut Vec<(usize, String)>,
+    ) {
+        match self {
+            DuplicateKeys::Error | DuplicateKeys::LastWins => {
+                map.insert(key, value);
+            }
+            DuplicateKeys::FirstWins => {
+                if !map.contains_key(&key) {
+                    map.insert(key, value);
+                }
+            }
+            DuplicateKeys::Collect => match map.get_mut(&key) {
+                None => {
+                    map.insert(key, value);
+                }
+                Some(JSON::Array(values))
+                    if collected.iter().any(|(d, k)| *d == depth && *k == key) =>
+                {
+                    values.push(value)
+                }
+                Some(first) => {
+                    let old = std::mem::replace(first, JSON::Null);
+                    *first = JSON::Array(vec![old, value]);
+                    collected.push((depth, key));
+                }
+            },
+        }
+    }
+}
+
+// Finds the first repeated key, for `DuplicateKeys::Error`
+pub(crate) struct DuplicateCheck {
+    // The keys of each open object and where they were, `None` for arrays
+    open: Vec<Option<HashMap<String, Position>>>,
+}
+
+impl DuplicateCheck {
+    // `None` unless `policy` is `Error`
+    pub(crate) fn new(policy: DuplicateKeys) -> Option<DuplicateCheck> {
+        (policy == DuplicateKeys::Error).then(|| DuplicateCheck { open: Vec::new() })
+    }
+
+    pub(crate) fn check(&mut self, event: &Event, at: Position) -> Result<(), JSONError> {
+        match event {
+            Event::StartObject => self.open.push(Some(HashMap::new())),
+            Event::StartArray => self.open.push(None),
+            Event::EndObject | Event::EndArray => {
+                self.open.pop();
+            }
+            Event::Key(key) => {
+                if let Some(Some(seen)) = self.open.last_mut() {
+                    match seen.entry(key.to_string()) {
+                        Entry::Occupied(first) => {
+                            return Err(JSONError::DuplicateKey(
+                                first.key().clone(),
+                                *first.get(),
+                                at,
+                            ))
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(at);
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
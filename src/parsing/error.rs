@@ -0,0 +1,266 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use super::Position;
+
+// Every error carries the position it was found at: for unexpected input the
+// offending character, for the end of input the end of the document and for
+// IO errors how far reading got.
+#[derive(Debug)]
+pub enum JSONError {
+    UnexpectedCharacter(char, Position),
+    UnexpectedEndOfInput(Position),
+    InvalidEscape(char, Position),
+    LoneSurrogate(u16, Position),
+    ParseError(ErrorKind, Position),
+    Io(std::io::Error, Position),
+    // A limit from `ParseOptions` was exceeded; each carries the limit
+    DepthLimitExceeded(usize, Position),
+    StringTooLong(usize, Position),
+    NumberTooLong(usize, Position),
+    TooManyElements(usize, Position),
+    TooManyValues(usize, Position),
+    InputTooLarge(usize, Position),
+    // A key repeated in an object, with where it first appeared and where again
+    DuplicateKey(String, Position, Position),
+}
+
+// What went wrong, without the details. `ParseError` covers tokens that are
+// well formed but out of place or, for numbers too large for an f64, out of
+// range, which is everything from `UnexpectedStartOfObject`
+// up to `InvalidUtf8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    UnexpectedCharacter,
+    UnexpectedEndOfInput,
+    InvalidEscape,
+    LoneSurrogate,
+    UnexpectedStartOfObject,
+    UnexpectedStartOfArray,
+    UnexpectedEndOfObject,
+    UnexpectedEndOfArray,
+    UnexpectedColon,
+    UnexpectedComma,
+    TrailingCommaInObject,
+    TrailingCommaInArray,
+    NonStringKey,
+    UnexpectedBool,
+    UnexpectedNull,
+    UnexpectedString,
+    UnexpectedNumber,
+    MultipleValues,
+    KeyInsteadOfValue,
+    NumberOutOfRange,
+    InvalidUtf8,
+    Io,
+    DepthLimitExceeded,
+    StringTooLong,
+    NumberTooLong,
+    TooManyElements,
+    TooManyValues,
+    InputTooLarge,
+    DuplicateKey,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::UnexpectedCharacter => "Unexpected character",
+            ErrorKind::UnexpectedEndOfInput => "Unexpected end of input",
+            ErrorKind::InvalidEscape => "Invalid escape sequence",
+            ErrorKind::LoneSurrogate => "Unpaired surrogate",
+            ErrorKind::UnexpectedStartOfObject => "Unexpected start of object",
+            ErrorKind::UnexpectedStartOfArray => "Unexpected start of array",
+            ErrorKind::UnexpectedEndOfObject => "Unexpected end curly brace",
+            ErrorKind::UnexpectedEndOfArray => "Unexpected end square brace",
+            ErrorKind::UnexpectedColon => "Unexpected colon",
+            ErrorKind::UnexpectedComma => "Unexpected comma",
+            ErrorKind::TrailingCommaInObject => "Trailing comma in object",
+            ErrorKind::TrailingCommaInArray => "Trailing comma in array",
+            ErrorKind::NonStringKey => "Non string used as object key",
+            ErrorKind::UnexpectedBool => "Unexpected boolean literal",
+            ErrorKind::UnexpectedNull => "Unexpected null value",
+            ErrorKind::UnexpectedString => "Unexpected string",
+            ErrorKind::UnexpectedNumber => "Unexpected number",
+            ErrorKind::MultipleValues => "Multiple independent JSON objects present",
+            ErrorKind::KeyInsteadOfValue => "Expected a value, found a key",
+            ErrorKind::NumberOutOfRange => "Number out of range",
+            ErrorKind::InvalidUtf8 => "Input is not valid UTF-8",
+            ErrorKind::Io => "IO error",
+            ErrorKind::DepthLimitExceeded => "Nesting too deep",
+            ErrorKind::StringTooLong => "String too long",
+            ErrorKind::NumberTooLong => "Number too long",
+            ErrorKind::TooManyElements => "Too many elements in array or object",
+            ErrorKind::TooManyValues => "Too many values",
+            ErrorKind::InputTooLarge => "Input too large",
+            ErrorKind::DuplicateKey => "Duplicate key",
+        })
+    }
+}
+
+impl JSONError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            JSONError::UnexpectedCharacter(..) => ErrorKind::UnexpectedCharacter,
+            JSONError::UnexpectedEndOfInput(_) => ErrorKind::UnexpectedEndOfInput,
+            JSONError::InvalidEscape(..) => ErrorKind::InvalidEscape,
+            JSONError::LoneSurrogate(..) => ErrorKind::LoneSurrogate,
+            JSONError::ParseError(kind, _) => *kind,
+            JSONError::Io(..) => ErrorKind::Io,
+            JSONError::DepthLimitExceeded(..) => ErrorKind::DepthLimitExceeded,
+            JSONError::StringTooLong(..) => ErrorKind::StringTooLong,
+            JSONError::NumberTooLong(..) => ErrorKind::NumberTooLong,
+            JSONError::TooManyElements(..) => ErrorKind::TooManyElements,
+            JSONError::TooManyValues(..) => ErrorKind::TooManyValues,
+            JSONError::InputTooLarge(..) => ErrorKind::InputTooLarge,
+            JSONError::DuplicateKey(..) => ErrorKind::DuplicateKey,
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        match self {
+            JSONError::UnexpectedCharacter(_, p)
+            | JSONError::UnexpectedEndOfInput(p)
+            | JSONError::InvalidEscape(_, p)
+            | JSONError::LoneSurrogate(_, p)
+            | JSONError::ParseError(_, p)
+            | JSONError::Io(_, p)
+            | JSONError::DepthLimitExceeded(_, p)
+            | JSONError::StringTooLong(_, p)
+            | JSONError::NumberTooLong(_, p)
+            | JSONError::TooManyElements(_, p)
+            | JSONError::TooManyValues(_, p)
+            | JSONError::InputTooLarge(_, p)
+            | JSONError::DuplicateKey(_, _, p) => *p,
+        }
+    }
+
+    fn position_mut(&mut self) -> &mut Position {
+        match self {
+            JSONError::UnexpectedCharacter(_, p)
+            | JSONError::UnexpectedEndOfInput(p)
+            | JSONError::InvalidEscape(_, p)
+            | JSONError::LoneSurrogate(_, p)
+            | JSONError::ParseError(_, p)
+            | JSONError::Io(_, p)
+            | JSONError::DepthLimitExceeded(_, p)
+            | JSONError::StringTooLong(_, p)
+            | JSONError::NumberTooLong(_, p)
+            | JSONError::TooManyElements(_, p)
+            | JSONError::TooManyValues(_, p)
+            | JSONError::InputTooLarge(_, p)
+            | JSONError::DuplicateKey(_, _, p) => p,
+        }
+    }
+
+    // Moves the position of an error found in a slice of a larger input that
+    // starts `lines` lines and `bytes` bytes into it, at the start of a line
+    pub(crate) fn offset_by(mut self, lines: usize, bytes: usize) -> JSONError {
+        let position = self.position_mut();
+        position.line += lines;
+        position.offset += bytes;
+        if let JSONError::DuplicateKey(_, first, _) = &mut self {
+            first.line += lines;
+            first.offset += bytes;
+        }
+        self
+    }
+
+    // The error without its position
+    fn message(&self) -> String {
+        match self {
+            JSONError::UnexpectedCharacter(c, _) => format!("Unexpected character '{}'", c),
+            JSONError::InvalidEscape(c, _) => format!("Invalid escape sequence '\\{}'", c),
+            JSONError::LoneSurrogate(u, _) => format!("Unpaired surrogate '\\u{:04X}'", u),
+            JSONError::Io(e, _) => format!("IO error: {}", e),
+            JSONError::DepthLimitExceeded(max, _) => {
+                format!("Nesting deeper than the limit of {}", max)
+            }
+            JSONError::StringTooLong(max, _) => {
+                format!("String longer than the limit of {} bytes", max)
+            }
+            JSONError::NumberTooLong(max, _) => {
+                format!("Number longer than the limit of {} bytes", max)
+            }
+            JSONError::TooManyElements(max, _) => {
+                format!(
+                    "Array or object with more than the limit of {} elements",
+                    max
+                )
+            }
+            JSONError::TooManyValues(max, _) => {
+                format!("More values than the limit of {}", max)
+            }
+            JSONError::InputTooLarge(max, _) => {
+                format!("Input larger than the limit of {} bytes", max)
+            }
+            JSONError::DuplicateKey(key, first, _) => format!(
+                "Duplicate key {:?} (first at line {} position {})",
+                key, first.line, first.column
+            ),
+            e => e.kind().to_string(),
+        }
+    }
+
+    // Shows the line of `source` the error is on with a caret under the
+    // offending character, the way rustc does:
+    //
+    //     error: Unexpected comma
+    //      --> 1:9
+    //       |
+    //     1 | {"a": 1,,}
+    //       |         ^
+    //
+    // `source` must be the parsed input. Tabs are kept so the caret lines up.
+    pub fn render(&self, source: &str) -> String {
+        let position = self.position();
+        let mut offset = position.offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find(['\n', '\r'])
+            .map_or(source.len(), |i| offset + i);
+
+        let number = position.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let padding: String = source[line_start..offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let mut out = String::new();
+        writeln!(out, "error: {}", self.message()).expect("Writing to a String");
+        writeln!(out, "{}--> {}:{}", gutter, position.line, position.column)
+            .expect("Writing to a String");
+        writeln!(out, "{} |", gutter).expect("Writing to a String");
+        writeln!(out, "{} | {}", number, &source[line_start..line_end])
+            .expect("Writing to a String");
+        writeln!(out, "{} | {}^", gutter, padding).expect("Writing to a String");
+        out
+    }
+}
+
+impl Display for JSONError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let position = self.position();
+        if let JSONError::ParseError(..) = self {
+            f.write_str("Parse error: ")?;
+        }
+        write!(
+            f,
+            "{} at line {} position {}",
+            self.message(),
+            position.line,
+            position.column
+        )
+    }
+}
+
+impl std::error::Error for JSONError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JSONError::Io(e, _) => Some(e),
+            _ => None,
+        }
+    }
+}
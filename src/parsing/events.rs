@@ -0,0 +1,198 @@
+use std::borrow::Cow;
+
+use super::{Dialect, ErrorKind, Grammar, JSONError, TokenIterator, ValueBuilder};
+use crate::json::{Number, JSON};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(Cow<'a, str>),
+    String(Cow<'a, str>),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+// Lines and columns start at 1 and columns count bytes; `offset` is the byte
+// offset into the input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    pub(crate) fn new(line: usize, column: usize, offset: usize) -> Position {
+        Position {
+            line,
+            column,
+            offset,
+        }
+    }
+
+    // The position `bytes` further along the same line
+    pub(crate) fn advance(self, bytes: usize) -> Position {
+        Position::new(self.line, self.column + bytes, self.offset + bytes)
+    }
+}
+
+// A comment in JSONC input, with its `//` or `/* */` delimiters, and the
+// position of its first character
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment<'a> {
+    pub text: &'a str,
+    pub position: Position,
+}
+
+impl<'a> Comment<'a> {
+    pub(crate) fn new(text: &'a str, position: Position) -> Comment<'a> {
+        Comment { text, position }
+    }
+
+    pub fn is_block(&self) -> bool {
+        self.text.starts_with("/*")
+    }
+
+    // The text between the delimiters
+    pub fn content(&self) -> &'a str {
+        if self.is_block() {
+            &self.text[2..self.text.len() - 2]
+        } else {
+            &self.text[2..]
+        }
+    }
+}
+
+// A pull parser over a JSON document. Each call to `next` yields the next
+// event together with the position of the token it came from. Structure is
+// validated as the document is read, so errors only surface when reached and
+// the iterator stops after the first one.
+pub struct EventReader<'a> {
+    tokens: TokenIterator<'a>,
+    grammar: Grammar,
+    finished: bool,
+    comments: Option<Vec<Comment<'a>>>,
+}
+
+impl<'a> EventReader<'a> {
+    pub fn new(s: &'a str) -> EventReader<'a> {
+        EventReader {
+            tokens: TokenIterator::new(s),
+            grammar: Grammar::new(),
+            finished: false,
+            comments: None,
+        }
+    }
+
+    // Reads JSONC, skipping `//` and `/* */` comments and allowing trailing commas
+    pub fn jsonc(s: &'a str) -> EventReader<'a> {
+        EventReader {
+            tokens: TokenIterator::with_dialect(s, Dialect::Jsonc),
+            grammar: Grammar::with_dialect(Dialect::Jsonc),
+            finished: false,
+            comments: None,
+        }
+    }
+
+    // Keeps the comments that are skipped so `take_comments` can return them
+    pub fn with_comments(mut self) -> EventReader<'a> {
+        self.comments = Some(Vec::new());
+        self
+    }
+
+    // The comments read since the last call. Those before an event are
+    // available once it has been returned, so a caller can attach them to it.
+    pub fn take_comments(&mut self) -> Vec<Comment<'a>> {
+        self.comments
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    // Reads the next value of the enclosing array or object (or the whole
+    // document) as a `JSON` tree. Returns `None` if the container ends instead.
+    pub fn read_value(&mut self) -> Result<Option<JSON>, JSONError> {
+        let mut builder = ValueBuilder::new();
+        let mut first = true;
+        loop {
+            let (event, at) = match self.next() {
+                Some(event) => event?,
+                None => return Ok(None),
+            };
+            if first {
+                match event {
+                    Event::EndObject | Event::EndArray => return Ok(None),
+                    Event::Key(_) => {
+                        return Err(JSONError::ParseError(ErrorKind::KeyInsteadOfValue, at))
+                    }
+                    _ => first = false,
+                }
+            }
+            if let Some(value) = builder.push(event) {
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    // Skips past the next value without building it
+    pub fn skip_value(&mut self) -> Result<(), JSONError> {
+        let mut depth = 0usize;
+        for event in self.by_ref() {
+            match event?.0 {
+                Event::StartObject | Event::StartArray => depth += 1,
+                Event::EndObject | Event::EndArray => {
+                    if depth <= 1 {
+                        return Ok(());
+                    }
+                    depth -= 1;
+                }
+                Event::Key(_) => (),
+                _ if depth == 0 => return Ok(()),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for EventReader<'a> {
+    type Item = Result<(Event<'a>, Position), JSONError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            let token = match self.tokens.next() {
+                Some(Ok(token)) => token,
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+                None => {
+                    self.finished = true;
+                    return self.grammar.finish(self.tokens.end()).err().map(Err);
+                }
+            };
+            let position = self.tokens.position();
+            if let Some(comments) = self.comments.as_mut() {
+                if self.grammar.dialect.comments() && token.starts_with('/') {
+                    comments.push(Comment::new(token, position));
+                    continue;
+                }
+            }
+            match self.grammar.accept(token, position) {
+                Ok(Some(event)) => return Some(Ok((event, position))),
+                Ok(None) => continue,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
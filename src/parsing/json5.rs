@@ -0,0 +1,243 @@
+use std::borrow::Cow;
+
+use super::{strings, ErrorKind, Event, JSONError, Position};
+use crate::json::Number;
+
+pub(crate) fn scalar_event(st: &str, at: Position) -> Result<Event<'_>, JSONError> {
+    match st {
+        "true" => Ok(Event::Bool(true)),
+        "false" => Ok(Event::Bool(false)),
+        "null" => Ok(Event::Null),
+        _ if st.starts_with(['"', '\'']) => Ok(Event::String(decode_string(
+            &st[1..st.len() - 1],
+            at.advance(1),
+        )?)),
+        _ => parse_number(st, at).map(Event::Number),
+    }
+}
+
+// Object keys are strings or ECMAScript identifier names like `$key_1`
+pub(crate) fn decode_key(st: &str, at: Position) -> Result<Cow<'_, str>, JSONError> {
+    if st.starts_with(['"', '\'']) {
+        decode_string(&st[1..st.len() - 1], at.advance(1))
+    } else {
+        decode_identifier(st, at)
+    }
+}
+
+fn decode_identifier(st: &str, at: Position) -> Result<Cow<'_, str>, JSONError> {
+    let valid = |c: char, first: bool| {
+        c == '$'
+            || c == '_'
+            || c.is_alphabetic()
+            || (!first && (c.is_alphanumeric() || c == '\u{200C}' || c == '\u{200D}'))
+    };
+    if !st.contains('\\') {
+        return match st.char_indices().find(|&(i, c)| !valid(c, i == 0)) {
+            Some((i, c)) => Err(JSONError::UnexpectedCharacter(c, at.advance(i))),
+            None => Ok(Cow::Borrowed(st)),
+        };
+    }
+
+    let mut out = String::with_capacity(st.len());
+    let mut chars = st.char_indices();
+    while let Some((i, c)) = chars.next() {
+        let decoded = if c == '\\' {
+            if !st[i + 1..].starts_with('u') {
+                return Err(JSONError::UnexpectedCharacter(c, at.advance(i)));
+            }
+            let code = strings::read_hex(st, i + 2, 4, at.advance(i), 'u')?;
+            chars.nth(4);
+            char::from_u32(code).ok_or(JSONError::LoneSurrogate(code as u16, at.advance(i)))?
+        } else {
+            c
+        };
+        if !valid(decoded, i == 0) {
+            return Err(JSONError::UnexpectedCharacter(c, at.advance(i)));
+        }
+        out.push(decoded);
+    }
+    Ok(Cow::Owned(out))
+}
+
+// Decodes the contents of a single or double quoted JSON5 string. On top of
+// JSON's escapes these allow `\'`, `\v`, `\0`, `\xHH`, any other character
+// escaping itself and a backslash before a line break to continue the string.
+pub(crate) fn decode_string(raw: &str, at: Position) -> Result<Cow<'_, str>, JSONError> {
+    if !raw.contains(['\\', '\n', '\r']) {
+        return Ok(Cow::Borrowed(raw));
+    }
+
+    // The column of `raw[i]` is `origin + i`, until a line continuation moves it
+    let mut line = at.line;
+    let mut origin = at.column as isize;
+    let position = |line: usize, origin: isize, i: usize| {
+        Position::new(line, (origin + i as isize) as usize, at.offset + i)
+    };
+
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' | '\r' => {
+                return Err(JSONError::UnexpectedCharacter(c, position(line, origin, i)));
+            }
+            '\\' => (),
+            c => {
+                out.push(c);
+                continue;
+            }
+        }
+
+        let at = position(line, origin, i);
+        let esc = match chars.next() {
+            Some((_, e)) => e,
+            None => return Err(JSONError::InvalidEscape('\\', at)),
+        };
+        match esc {
+            'b' => out.push('\x08'),
+            'f' => out.push('\x0c'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\x0b'),
+            '0' if !matches!(chars.peek(), Some((_, '0'..='9'))) => out.push('\0'),
+            '0'..='9' => return Err(JSONError::InvalidEscape(esc, at)),
+            'x' => {
+                let code = strings::read_hex(raw, i + 2, 2, at, 'x')?;
+                chars.nth(1);
+                out.push(char::from_u32(code).expect("Below 0x100"));
+            }
+            'u' => {
+                let (c, len) = strings::decode_unicode_escape(raw, i, at)?;
+                out.push(c);
+                chars.nth(len - 3);
+            }
+            // Line continuations, which leave nothing in the string
+            '\n' => {
+                line += 1;
+                origin = 1 - (i + 2) as isize;
+            }
+            '\r' => {
+                if let Some((j, '\n')) = chars.peek().copied() {
+                    chars.next();
+                    line += 1;
+                    origin = 1 - (j + 1) as isize;
+                }
+            }
+            '\u{2028}' | '\u{2029}' => (),
+            e => out.push(e),
+        }
+    }
+    Ok(Cow::Owned(out))
+}
+
+// JSON5 numbers: JSON's plus a leading `+`, hexadecimal integers, `.5` and `5.`,
+// `Infinity` and `NaN`. Numbers too large for an f64 are out of range rather
+// than infinite, as in JSON.
+pub(crate) fn parse_number(st: &str, at: Position) -> Result<Number, JSONError> {
+    let (negative, sign_len) = match st.as_bytes().first() {
+        Some(b'-') => (true, 1),
+        Some(b'+') => (false, 1),
+        _ => (false, 0),
+    };
+    let rest = &st[sign_len..];
+    let bad = |offset: usize| {
+        JSONError::UnexpectedCharacter(
+            st[offset..].chars().next().expect("In bounds"),
+            at.advance(offset),
+        )
+    };
+    // Past the end, the last character is reported
+    let fail = |offset: usize| {
+        if offset < st.len() {
+            bad(offset)
+        } else {
+            bad(st.char_indices().last().map_or(0, |(i, _)| i))
+        }
+    };
+    let out_of_range = JSONError::ParseError(ErrorKind::NumberOutOfRange, at);
+
+    match rest {
+        "Infinity" if negative => return Ok(Number::from_f64(f64::NEG_INFINITY)),
+        "Infinity" => return Ok(Number::from_f64(f64::INFINITY)),
+        "NaN" => return Ok(Number::from_f64(f64::NAN)),
+        _ => (),
+    }
+
+    if rest.starts_with("0x") || rest.starts_with("0X") {
+        let digits = &rest[2..];
+        if digits.is_empty() {
+            return Err(fail(st.len()));
+        }
+        if let Some(i) = digits.bytes().position(|b| !b.is_ascii_hexdigit()) {
+            return Err(bad(sign_len + 2 + i));
+        }
+        return Ok(match u64::from_str_radix(digits, 16) {
+            Ok(0) if negative => Number::from_f64(-0.0),
+            Ok(u) if negative => match i64::try_from(u) {
+                Ok(i) => Number::from(-i),
+                Err(_) if u == 1 << 63 => Number::from(i64::MIN),
+                Err(_) => Number::from_f64(-(u as f64)),
+            },
+            Ok(u) => Number::from(u),
+            Err(_) => {
+                let f = digits.bytes().fold(0.0, |acc, b| {
+                    acc * 16.0 + (b as char).to_digit(16).expect("Checked above") as f64
+                });
+                if f.is_infinite() {
+                    return Err(out_of_range);
+                }
+                Number::from_f64(if negative { -f } else { f })
+            }
+        });
+    }
+
+    let bytes = rest.as_bytes();
+    let digits_from = |mut i: usize| {
+        while let Some(b'0'..=b'9') = bytes.get(i) {
+            i += 1;
+        }
+        i
+    };
+    let int_end = digits_from(0);
+    if int_end > 1 && bytes[0] == b'0' {
+        return Err(bad(sign_len + 1));
+    }
+    let (frac_start, frac_end) = if bytes.get(int_end) == Some(&b'.') {
+        (int_end + 1, digits_from(int_end + 1))
+    } else {
+        (int_end, int_end)
+    };
+    if int_end == 0 && frac_end == frac_start {
+        return Err(fail(sign_len + frac_end));
+    }
+    let mut end = frac_end;
+    if let Some(b'e' | b'E') = bytes.get(end) {
+        end += 1;
+        if let Some(b'+' | b'-') = bytes.get(end) {
+            end += 1;
+        }
+        let exp_end = digits_from(end);
+        if exp_end == end {
+            return Err(fail(sign_len + end));
+        }
+        end = exp_end;
+    }
+    if end != bytes.len() {
+        return Err(bad(sign_len + end));
+    }
+
+    // Rewritten in JSON's number syntax
+    let mut text = String::with_capacity(st.len() + 2);
+    if negative {
+        text.push('-');
+    }
+    text.push_str(if int_end == 0 { "0" } else { &rest[..int_end] });
+    if frac_end > frac_start {
+        text.push('.');
+        text.push_str(&rest[frac_start..frac_end]);
+    }
+    text.push_str(&rest[frac_end..end]);
+    Number::from_validated_str(&text).ok_or(out_of_range)
+}
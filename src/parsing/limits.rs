@@ -0,0 +1,157 @@
+use super::{DuplicateKeys, Event, JSONError, Position, DEFAULT_MAX_DEPTH};
+
+// Caps on how much work parsing untrusted input may do, and what to do with
+// repeated object keys. Every limit but `max_depth` is off by default. Going
+// over one fails the parse with its own error, reported at the token that went
+// over it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    // Arrays and objects open at once; a top-level array is at depth 1. Defaults
+    // to `DEFAULT_MAX_DEPTH`; without a limit, deep input can overflow the stack.
+    pub max_depth: Option<usize>,
+    // Length of a single string or key once unescaped
+    pub max_string_bytes: Option<usize>,
+    // Length of a single number as written
+    pub max_number_bytes: Option<usize>,
+    // Items in one array or members in one object
+    pub max_container_elements: Option<usize>,
+    // Values in the whole document, arrays and objects included
+    pub max_values: Option<usize>,
+    pub max_input_bytes: Option<usize>,
+    pub duplicate_keys: DuplicateKeys,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_string_bytes: None,
+            max_number_bytes: None,
+            max_container_elements: None,
+            max_values: None,
+            max_input_bytes: None,
+            duplicate_keys: DuplicateKeys::default(),
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    // Fails if `s` is longer than `max_input_bytes`, at the first byte past it
+    pub(crate) fn check_input(&self, s: &str) -> Result<(), JSONError> {
+        match self.max_input_bytes {
+            Some(max) if s.len() > max => {
+                let before = &s.as_bytes()[..max];
+                let line_start = before
+                    .iter()
+                    .rposition(|&b| b == b'\n')
+                    .map_or(0, |i| i + 1);
+                let line = 1 + before.iter().filter(|&&b| b == b'\n').count();
+                let at = Position::new(line, max - line_start + 1, max);
+                Err(JSONError::InputTooLarge(max, at))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Whether events need to be checked at all; depth is checked by `Grammar`
+    fn checks_events(&self) -> bool {
+        self.max_string_bytes.is_some()
+            || self.max_number_bytes.is_some()
+            || self.max_container_elements.is_some()
+            || self.max_values.is_some()
+    }
+}
+
+// Checks parse events against the limits in `ParseOptions` as they are read
+pub(crate) struct Limiter<'o> {
+    options: &'o ParseOptions,
+    // Elements read so far in each open container, flagged true for objects
+    open: Vec<(bool, usize)>,
+    values: usize,
+}
+
+impl<'o> Limiter<'o> {
+    // `None` if there is nothing to check
+    pub(crate) fn new(options: &'o ParseOptions) -> Option<Limiter<'o>> {
+        options.checks_events().then(|| Limiter {
+            options,
+            open: Vec::new(),
+            values: 0,
+        })
+    }
+
+    // `token` is the text `event` was read from
+    pub(crate) fn check(
+        &mut self,
+        event: &Event,
+        token: &str,
+        at: Position,
+    ) -> Result<(), JSONError> {
+        match event {
+            Event::StartObject | Event::StartArray => {
+                self.value(at)?;
+                self.open.push((*event == Event::StartObject, 0));
+            }
+            Event::EndObject | Event::EndArray => {
+                self.open.pop();
+            }
+            Event::Key(key) => {
+                self.string(key, at)?;
+                self.element(at)?;
+            }
+            Event::String(s) => {
+                self.string(s, at)?;
+                self.value(at)?;
+            }
+            Event::Number(_) => {
+                if let Some(max) = self
+                    .options
+                    .max_number_bytes
+                    .filter(|&max| token.len() > max)
+                {
+                    return Err(JSONError::NumberTooLong(max, at));
+                }
+                self.value(at)?;
+            }
+            _ => self.value(at)?,
+        }
+        Ok(())
+    }
+
+    // Object members are counted by their keys, array items here
+    fn value(&mut self, at: Position) -> Result<(), JSONError> {
+        self.values += 1;
+        if let Some(max) = self.options.max_values.filter(|&max| self.values > max) {
+            return Err(JSONError::TooManyValues(max, at));
+        }
+        if let Some((false, _)) = self.open.last() {
+            self.element(at)?;
+        }
+        Ok(())
+    }
+
+    fn element(&mut self, at: Position) -> Result<(), JSONError> {
+        if let Some((_, count)) = self.open.last_mut() {
+            *count += 1;
+            if let Some(max) = self
+                .options
+                .max_container_elements
+                .filter(|&max| *count > max)
+            {
+                return Err(JSONError::TooManyElements(max, at));
+            }
+        }
+        Ok(())
+    }
+
+    fn string(&self, s: &str, at: Position) -> Result<(), JSONError> {
+        match self.options.max_string_bytes {
+            Some(max) if s.len() > max => Err(JSONError::StringTooLong(max, at)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    str::FromStr,
+};
+
+use super::{ErrorKind, JSONError, Position};
+use crate::json::JSON;
+
+// Parses one line of newline-delimited JSON that starts `offset` bytes into
+// the document. Blank lines yield `None`.
+fn parse_line(line: &str, line_no: usize, offset: usize) -> Option<Result<JSON, JSONError>> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    if line.trim().is_empty() {
+        return None;
+    }
+    Some(JSON::from_str(line).map_err(|e| e.offset_by(line_no - 1, offset)))
+}
+
+// Iterates over the values of a newline-delimited JSON (JSON Lines) document,
+// one per line. Errors report the line they occurred on within the document.
+pub struct NDJSONIter<'a> {
+    lines: std::str::Split<'a, char>,
+    line_no: usize,
+    // Byte offset of the next line
+    offset: usize,
+    skip_invalid: bool,
+}
+
+impl<'a> NDJSONIter<'a> {
+    pub fn new(s: &'a str) -> NDJSONIter<'a> {
+        NDJSONIter {
+            lines: s.split('\n'),
+            line_no: 0,
+            offset: 0,
+            skip_invalid: false,
+        }
+    }
+
+    // Silently drop lines that fail to parse instead of yielding their errors
+    pub fn skip_invalid(mut self, skip: bool) -> NDJSONIter<'a> {
+        self.skip_invalid = skip;
+        self
+    }
+
+    // Line number of the most recently yielded value or error
+    pub fn line_number(&self) -> usize {
+        self.line_no
+    }
+}
+
+impl Iterator for NDJSONIter<'_> {
+    type Item = Result<JSON, JSONError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?;
+            self.line_no += 1;
+            let offset = self.offset;
+            self.offset += line.len() + 1;
+            match parse_line(line, self.line_no, offset) {
+                None => continue,
+                Some(Err(_)) if self.skip_invalid => continue,
+                Some(result) => return Some(result),
+            }
+        }
+    }
+}
+
+// The same as `NDJSONIter`, reading one line at a time from an `io::Read`.
+// IO errors are always yielded and end the iteration.
+pub struct NDJSONReader<R: Read> {
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    line_no: usize,
+    offset: usize,
+    skip_invalid: bool,
+    failed: bool,
+}
+
+impl<R: Read> NDJSONReader<R> {
+    pub fn new(reader: R) -> NDJSONReader<R> {
+        NDJSONReader {
+            reader: BufReader::new(reader),
+            buf: Vec::new(),
+            line_no: 0,
+            offset: 0,
+            skip_invalid: false,
+            failed: false,
+        }
+    }
+
+    pub fn skip_invalid(mut self, skip: bool) -> NDJSONReader<R> {
+        self.skip_invalid = skip;
+        self
+    }
+
+    pub fn line_number(&self) -> usize {
+        self.line_no
+    }
+}
+
+impl<R: Read> Iterator for NDJSONReader<R> {
+    type Item = Result<JSON, JSONError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.failed = true;
+                    let at = Position::new(self.line_no + 1, 1, self.offset);
+                    return Some(Err(JSONError::Io(e, at)));
+                }
+            }
+            self.line_no += 1;
+            let offset = self.offset;
+            self.offset += self.buf.len();
+
+            let line = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+            let result = match std::str::from_utf8(line) {
+                Ok(line) => parse_line(line, self.line_no, offset),
+                Err(e) => {
+                    let column = e.valid_up_to();
+                    let at = Position::new(self.line_no, column + 1, offset + column);
+                    Some(Err(JSONError::ParseError(ErrorKind::InvalidUtf8, at)))
+                }
+            };
+            match result {
+                None => continue,
+                Some(Err(_)) if self.skip_invalid => continue,
+                Some(result) => return Some(result),
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,51 @@
+// Checks `s` against the RFC 8259 number grammar:
+// -? (0 | [1-9][0-9]*) (\.[0-9]+)? ([eE][+-]?[0-9]+)?
+// On failure returns the byte offset of the offending character.
+pub(crate) fn scan_number(s: &str) -> Result<(), usize> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+
+    if bytes.get(i) == Some(&b'-') {
+        i += 1;
+    }
+
+    match bytes.get(i) {
+        Some(b'0') => i += 1,
+        Some(b'1'..=b'9') => i = skip_digits(bytes, i + 1),
+        _ => return Err(i.min(s.len().saturating_sub(1))),
+    }
+
+    if bytes.get(i) == Some(&b'.') {
+        i = require_digits(bytes, i + 1)?;
+    }
+
+    if let Some(b'e' | b'E') = bytes.get(i) {
+        i += 1;
+        if let Some(b'+' | b'-') = bytes.get(i) {
+            i += 1;
+        }
+        i = require_digits(bytes, i)?;
+    }
+
+    if i == bytes.len() {
+        Ok(())
+    } else {
+        Err(i)
+    }
+}
+
+fn skip_digits(bytes: &[u8], mut i: usize) -> usize {
+    while let Some(b'0'..=b'9') = bytes.get(i) {
+        i += 1;
+    }
+    i
+}
+
+fn require_digits(bytes: &[u8], i: usize) -> Result<usize, usize> {
+    let end = skip_digits(bytes, i);
+    if end == i {
+        Err(i.min(bytes.len() - 1))
+    } else {
+        Ok(end)
+    }
+}
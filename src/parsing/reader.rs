@@ -0,0 +1,130 @@
+use std::io::Read;
+
+use super::{ErrorKind, JSONError, ParseOptions, Position, Scan, Scanner};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+// Tokenizes input from an `io::Read` through a buffer that only ever holds the
+// unread part of the current chunk plus the token being scanned, so a token
+// split across reads is carried over instead of keeping the whole input.
+pub(crate) struct ReadTokens<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    end: usize,
+    scanner: Scanner,
+    eof: bool,
+    // Bytes read so far and how many may be
+    total: usize,
+    limit: Option<usize>,
+    max_string_bytes: Option<usize>,
+    max_number_bytes: Option<usize>,
+}
+
+impl<R: Read> ReadTokens<R> {
+    pub(crate) fn new(reader: R) -> ReadTokens<R> {
+        ReadTokens {
+            reader,
+            buf: vec![0; CHUNK_SIZE],
+            end: 0,
+            scanner: Scanner::new(),
+            eof: false,
+            total: 0,
+            limit: None,
+            max_string_bytes: None,
+            max_number_bytes: None,
+        }
+    }
+
+    // Fails once more than `max_input_bytes` have been read, at the position
+    // scanning had got to, or once a token being read can only go over
+    // `max_string_bytes` or `max_number_bytes`, at its start. The token is
+    // checked again once complete.
+    pub(crate) fn with_limits(mut self, options: &ParseOptions) -> ReadTokens<R> {
+        self.limit = options.max_input_bytes;
+        self.max_string_bytes = options.max_string_bytes;
+        self.max_number_bytes = options.max_number_bytes;
+        self
+    }
+
+    // Yields each token with the position it starts at
+    pub(crate) fn next_token(&mut self) -> Option<Result<(&str, Position), JSONError>> {
+        loop {
+            match self.scanner.scan(&self.buf[..self.end], self.eof) {
+                Scan::Token(start) => {
+                    let token = &self.buf[start..self.scanner.pos()];
+                    let at = self.scanner.position();
+                    return Some(match std::str::from_utf8(token) {
+                        Ok(token) => Ok((token, at)),
+                        Err(e) => Err(JSONError::ParseError(
+                            ErrorKind::InvalidUtf8,
+                            at.advance(e.valid_up_to()),
+                        )),
+                    });
+                }
+                Scan::Error(e) => return Some(Err(e)),
+                Scan::End => return None,
+                Scan::NeedMore => {
+                    if let Err(e) = self.fill() {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+
+    // The end of input, once every token has been read
+    pub(crate) fn end(&self) -> Position {
+        self.scanner.here()
+    }
+
+    fn fill(&mut self) -> Result<(), JSONError> {
+        let keep_from = self.scanner.start();
+        self.buf.copy_within(keep_from..self.end, 0);
+        self.end -= keep_from;
+        self.scanner.shift(keep_from);
+        self.check_token()?;
+        if self.end == self.buf.len() {
+            self.buf.resize(self.buf.len() * 2, 0);
+        }
+
+        let read = loop {
+            match self.reader.read(&mut self.buf[self.end..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(JSONError::Io(e, self.scanner.here())),
+            }
+        };
+        self.end += read;
+        self.total += read;
+        if let Some(max) = self.limit.filter(|&max| self.total > max) {
+            return Err(JSONError::InputTooLarge(max, self.scanner.here()));
+        }
+        if read == 0 {
+            self.eof = true;
+        }
+        Ok(())
+    }
+
+    // Checks the part of a token read so far, which starts the buffer
+    fn check_token(&self) -> Result<(), JSONError> {
+        let len = self.scanner.pos();
+        if len == 0 {
+            return Ok(());
+        }
+        let at = self.scanner.token_start();
+        if self.buf[0] == b'"' {
+            // An escape is at most six bytes per byte it decodes to
+            match self.max_string_bytes {
+                Some(max) if len > max.saturating_mul(6).saturating_add(2) => {
+                    Err(JSONError::StringTooLong(max, at))
+                }
+                _ => Ok(()),
+            }
+        } else {
+            match self.max_number_bytes {
+                Some(max) if len > max => Err(JSONError::NumberTooLong(max, at)),
+                _ => Ok(()),
+            }
+        }
+    }
+}
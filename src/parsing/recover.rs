@@ -0,0 +1,350 @@
+use super::{
+    scalar_event, strings, ErrorKind, Event, Expect, Frame, JSONError, Position, TokenIterator,
+    DEFAULT_MAX_DEPTH,
+};
+use crate::json::{Map, JSON};
+
+impl JSON {
+    // Parses as much of `s` as it can, collecting every error instead of
+    // stopping at the first. Parsing picks up again at the next `,`, `}` or `]`:
+    // a missing comma or colon is assumed, a malformed value or member is left
+    // out, a mismatched bracket closes the containers it skips over and anything
+    // still open at the end is closed. Arrays and objects nested deeper than
+    // `DEFAULT_MAX_DEPTH` are reported and left out. Without errors the value is
+    // the one `from_str` returns.
+    pub fn from_str_recovering(s: &str) -> (Option<JSON>, Vec<JSONError>) {
+        let mut parser = Recovering {
+            tokens: TokenIterator::new(s),
+            stack: Vec::new(),
+            expect: Expect::Value,
+            result: None,
+            errors: Vec::new(),
+            extra_reported: false,
+            value_reported: false,
+            skipping: 0,
+        };
+        parser.run();
+        (parser.result, parser.errors)
+    }
+}
+
+struct Recovering<'a> {
+    tokens: TokenIterator<'a>,
+    stack: Vec<Frame>,
+    expect: Expect,
+    result: Option<JSON>,
+    errors: Vec<JSONError>,
+    // Only the first value after the top-level one is reported
+    extra_reported: bool,
+    // Set by a member's stray `:`, which already reports its missing value,
+    // until the next token
+    value_reported: bool,
+    // Brackets open in a value nested too deep, which is skipped
+    skipping: usize,
+}
+
+impl<'a> Recovering<'a> {
+    fn run(&mut self) {
+        let mut retry = None;
+        loop {
+            let (token, at) = match retry.take() {
+                Some(token) => token,
+                None => match self.tokens.next() {
+                    Some(Ok(token)) => (token, self.tokens.position()),
+                    // Only an unterminated string ends the input early
+                    Some(Err(e @ JSONError::UnexpectedEndOfInput(_))) => {
+                        self.errors.push(e);
+                        break;
+                    }
+                    // A stray character where a value should be stands in for it
+                    Some(Err(e)) => {
+                        self.errors.push(e);
+                        self.tokens.scanner.skip_byte();
+                        if matches!(self.expect, Expect::Value | Expect::FirstValueOrEnd) {
+                            self.value_done(None);
+                        }
+                        continue;
+                    }
+                    None => break,
+                },
+            };
+            if self.skipping > 0 {
+                self.skip(token);
+            } else if self.accept(token, at) {
+                retry = Some((token, at));
+            }
+        }
+
+        // An unterminated string has already been reported as the end of input
+        let open = !self.stack.is_empty() || self.expect != Expect::Done;
+        if open && !matches!(self.errors.last(), Some(JSONError::UnexpectedEndOfInput(_))) {
+            let end = self.tokens.end();
+            self.errors.push(JSONError::UnexpectedEndOfInput(end));
+        }
+        while !self.stack.is_empty() {
+            self.close_top();
+        }
+    }
+
+    // Handles one token, returning true if it should be handled again in the
+    // state it left the parser in
+    fn accept(&mut self, token: &'a str, at: Position) -> bool {
+        let value_reported = std::mem::take(&mut self.value_reported);
+        match (self.expect, token) {
+            (Expect::Done, "}" | "]" | "," | ":") => {
+                self.unexpected(token, at);
+                false
+            }
+            (Expect::Done, _) => {
+                if !self.extra_reported {
+                    self.extra_reported = true;
+                    self.error(ErrorKind::MultipleValues, at);
+                }
+                self.expect = Expect::Value;
+                true
+            }
+
+            (Expect::Value | Expect::FirstValueOrEnd, "{" | "[")
+                if self.stack.len() >= DEFAULT_MAX_DEPTH =>
+            {
+                self.errors
+                    .push(JSONError::DepthLimitExceeded(DEFAULT_MAX_DEPTH, at));
+                self.skipping = 1;
+                false
+            }
+            (Expect::Value | Expect::FirstValueOrEnd, "{") => {
+                self.stack.push(Frame::Object(Map::new(), None));
+                self.expect = Expect::FirstKeyOrEnd;
+                false
+            }
+            (Expect::Value | Expect::FirstValueOrEnd, "[") => {
+                self.stack.push(Frame::Array(Vec::new()));
+                self.expect = Expect::FirstValueOrEnd;
+                false
+            }
+            (Expect::FirstValueOrEnd, "]") => self.close(token, at),
+            (Expect::Value, "]") if matches!(self.stack.last(), Some(Frame::Array(_))) => {
+                self.error(ErrorKind::TrailingCommaInArray, at);
+                self.close(token, at)
+            }
+            (Expect::Value | Expect::FirstValueOrEnd, "," | "}" | "]") => {
+                // The value is missing; the token is handled after it. `close`
+                // reports brackets that don't match.
+                let mismatched = match self.stack.last() {
+                    None => {
+                        self.unexpected(token, at);
+                        return false;
+                    }
+                    Some(Frame::Array(_)) => token == "}",
+                    Some(Frame::Object(..)) => token == "]",
+                };
+                if !mismatched && !value_reported {
+                    self.unexpected(token, at);
+                }
+                self.value_done(None);
+                true
+            }
+            (Expect::Value | Expect::FirstValueOrEnd, ":") => {
+                self.error(ErrorKind::UnexpectedColon, at);
+                false
+            }
+            (Expect::Value | Expect::FirstValueOrEnd, st) => {
+                match scalar_event(st, at) {
+                    Ok(event) => self.value_done(Some(scalar_value(event))),
+                    Err(e) => {
+                        self.errors.push(e);
+                        self.value_done(None);
+                    }
+                }
+                false
+            }
+
+            (Expect::Key | Expect::FirstKeyOrEnd, "}") => {
+                if self.expect == Expect::Key {
+                    self.error(ErrorKind::TrailingCommaInObject, at);
+                }
+                self.close(token, at)
+            }
+            (Expect::Key | Expect::FirstKeyOrEnd, "]") => self.close(token, at),
+            (Expect::Key | Expect::FirstKeyOrEnd, ",") => {
+                self.error(ErrorKind::UnexpectedComma, at);
+                false
+            }
+            (Expect::Key | Expect::FirstKeyOrEnd, ":") => {
+                self.error(ErrorKind::UnexpectedColon, at);
+                self.expect = Expect::Value;
+                self.value_reported = true;
+                false
+            }
+            (Expect::Key | Expect::FirstKeyOrEnd, "{" | "[") => {
+                self.error(ErrorKind::NonStringKey, at);
+                self.expect = Expect::Value;
+                true
+            }
+            (Expect::Key | Expect::FirstKeyOrEnd, st) => {
+                let key = if st.starts_with('"') {
+                    match strings::decode_string(&st[1..st.len() - 1], at.advance(1)) {
+                        Ok(key) => Some(key.into_owned()),
+                        Err(e) => {
+                            self.errors.push(e);
+                            None
+                        }
+                    }
+                } else {
+                    self.error(ErrorKind::NonStringKey, at);
+                    None
+                };
+                if let Some(Frame::Object(_, pending)) = self.stack.last_mut() {
+                    *pending = key;
+                }
+                self.expect = Expect::Colon;
+                false
+            }
+
+            (Expect::Colon, ":") => {
+                self.expect = Expect::Value;
+                false
+            }
+            (Expect::Colon, "," | "}" | "]") => {
+                // The member has no value and is dropped
+                if token != "]" {
+                    self.unexpected(token, at);
+                }
+                self.value_done(None);
+                true
+            }
+            (Expect::Colon, _) => {
+                self.unexpected(token, at);
+                self.expect = Expect::Value;
+                true
+            }
+
+            (Expect::CommaOrEnd, ",") => {
+                self.expect = match self.stack.last() {
+                    Some(Frame::Object(..)) => Expect::Key,
+                    _ => Expect::Value,
+                };
+                false
+            }
+            (Expect::CommaOrEnd, "}" | "]") => self.close(token, at),
+            (Expect::CommaOrEnd, ":") => {
+                self.error(ErrorKind::UnexpectedColon, at);
+                false
+            }
+            (Expect::CommaOrEnd, _) => {
+                // A missing comma
+                self.unexpected(token, at);
+                self.expect = match self.stack.last() {
+                    Some(Frame::Object(..)) => Expect::Key,
+                    _ => Expect::Value,
+                };
+                true
+            }
+        }
+    }
+
+    // Passes over a value nested too deep, which counts as missing once its
+    // brackets are balanced
+    fn skip(&mut self, token: &str) {
+        match token {
+            "{" | "[" => self.skipping += 1,
+            "}" | "]" => {
+                self.skipping -= 1;
+                if self.skipping == 0 {
+                    self.value_done(None);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Closes the innermost array or object on `}` or `]`. A bracket that
+    // doesn't match it closes every container up to one it does match, or is
+    // skipped if there is none.
+    fn close(&mut self, token: &str, at: Position) -> bool {
+        let closes = |frame: &Frame| match frame {
+            Frame::Array(_) => token == "]",
+            Frame::Object(..) => token == "}",
+        };
+        match self.stack.iter().rposition(closes) {
+            Some(i) if i + 1 == self.stack.len() => {
+                self.close_top();
+                false
+            }
+            Some(i) => {
+                self.unexpected(token, at);
+                while self.stack.len() > i + 1 {
+                    self.close_top();
+                }
+                true
+            }
+            None => {
+                self.unexpected(token, at);
+                false
+            }
+        }
+    }
+
+    fn close_top(&mut self) {
+        let value = match self.stack.pop() {
+            Some(Frame::Array(arr)) => JSON::Array(arr),
+            Some(Frame::Object(map, _)) => JSON::Object(map),
+            None => return,
+        };
+        self.value_done(Some(value));
+    }
+
+    // Adds a finished value to its parent. `None` is a value that failed to
+    // parse, which drops its object member if it had one.
+    fn value_done(&mut self, value: Option<JSON>) {
+        match (self.stack.last_mut(), value) {
+            (None, Some(value)) if self.result.is_none() => self.result = Some(value),
+            (Some(Frame::Array(arr)), Some(value)) => arr.push(value),
+            (Some(Frame::Object(map, pending)), value) => {
+                if let (Some(key), Some(value)) = (pending.take(), value) {
+                    map.insert(key, value);
+                }
+            }
+            _ => (),
+        }
+        self.expect = if self.stack.is_empty() {
+            Expect::Done
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+
+    fn error(&mut self, kind: ErrorKind, at: Position) {
+        self.errors.push(JSONError::ParseError(kind, at));
+    }
+
+    // Reports a token that is out of place. A malformed literal or number is
+    // reported when it is parsed as a value instead.
+    fn unexpected(&mut self, token: &str, at: Position) {
+        let kind = match token {
+            "{" => ErrorKind::UnexpectedStartOfObject,
+            "[" => ErrorKind::UnexpectedStartOfArray,
+            "}" => ErrorKind::UnexpectedEndOfObject,
+            "]" => ErrorKind::UnexpectedEndOfArray,
+            ":" => ErrorKind::UnexpectedColon,
+            "," => ErrorKind::UnexpectedComma,
+            st => match scalar_event(st, at) {
+                Ok(Event::Bool(_)) => ErrorKind::UnexpectedBool,
+                Ok(Event::Null) => ErrorKind::UnexpectedNull,
+                Ok(Event::String(_)) => ErrorKind::UnexpectedString,
+                Ok(_) => ErrorKind::UnexpectedNumber,
+                Err(_) => return,
+            },
+        };
+        self.error(kind, at);
+    }
+}
+
+fn scalar_value(event: Event) -> JSON {
+    match event {
+        Event::String(s) => JSON::String(s.into_owned()),
+        Event::Number(n) => JSON::Number(n),
+        Event::Bool(b) => JSON::Bool(b),
+        _ => JSON::Null,
+    }
+}
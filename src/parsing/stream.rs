@@ -0,0 +1,163 @@
+use super::{ErrorKind, Grammar, JSONError, Position, TokenIterator, ValueBuilder};
+use crate::json::JSON;
+
+const RECORD_SEPARATOR: char = '\x1e';
+
+enum Mode {
+    Concatenated,
+    Sequence,
+}
+
+// Parses a series of top-level values from one input: either concatenated
+// (`{..}{..}[..]`, optionally separated by whitespace) or an RFC 7464 JSON text
+// sequence where each value is prefixed by an ASCII record separator.
+//
+// In concatenated mode the first error ends the iteration. In sequence mode a
+// malformed record is reported and parsing resumes at the next separator.
+pub struct StreamDeserializer<'a> {
+    s: &'a str,
+    mode: Mode,
+    tokens: TokenIterator<'a>,
+    // Byte offset of the slice `tokens` is reading within `s`
+    base: usize,
+    offset: usize,
+    line: usize,
+    char: usize,
+    finished: bool,
+}
+
+impl<'a> StreamDeserializer<'a> {
+    pub fn new(s: &'a str) -> StreamDeserializer<'a> {
+        StreamDeserializer {
+            s,
+            mode: Mode::Concatenated,
+            tokens: TokenIterator::new(s),
+            base: 0,
+            offset: 0,
+            line: 1,
+            char: 1,
+            finished: false,
+        }
+    }
+
+    pub fn json_seq(s: &'a str) -> StreamDeserializer<'a> {
+        let first = s.find(RECORD_SEPARATOR).unwrap_or(s.len());
+        StreamDeserializer {
+            mode: Mode::Sequence,
+            tokens: TokenIterator::new(&s[..first]),
+            ..StreamDeserializer::new(s)
+        }
+    }
+
+    // Byte offset just past the last value returned. After an
+    // `UnexpectedEndOfInput` error, input from here on can be kept and parsed
+    // again once more of it has arrived.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    fn next_value(&mut self) -> Option<Result<JSON, JSONError>> {
+        let mut grammar = Grammar::new();
+        let mut builder = ValueBuilder::new();
+        let mut started = false;
+        while let Some(token) = self.tokens.next() {
+            let token = match token {
+                Ok(token) => token,
+                Err(e) => return Some(Err(e)),
+            };
+            started = true;
+            match grammar.accept(token, self.tokens.position()) {
+                Ok(Some(event)) => {
+                    if let Some(value) = builder.push(event) {
+                        self.offset = self.base + self.tokens.pos();
+                        return Some(Ok(value));
+                    }
+                }
+                Ok(None) => (),
+                Err(_) if self.tokens.pos() == self.tokens.s.len() && is_truncated(token) => {
+                    return Some(Err(JSONError::UnexpectedEndOfInput(self.tokens.end())))
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if started {
+            Some(Err(JSONError::UnexpectedEndOfInput(self.tokens.end())))
+        } else {
+            None
+        }
+    }
+
+    // Moves `tokens` to the record after the next separator at or after `from`
+    fn next_record(&mut self, from: usize) -> bool {
+        let sep = match self.s[from..].find(RECORD_SEPARATOR) {
+            Some(i) => from + i,
+            None => return false,
+        };
+        for c in self.s[self.base..sep + 1].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.char = 1;
+            } else {
+                self.char += c.len_utf8();
+            }
+        }
+        self.base = sep + 1;
+        let end = self.s[self.base..]
+            .find(RECORD_SEPARATOR)
+            .map_or(self.s.len(), |i| self.base + i);
+        let at = Position::new(self.line, self.char, self.base);
+        self.tokens = TokenIterator::starting_at(&self.s[self.base..end], at);
+        true
+    }
+}
+
+// Whether a bare token at the very end of the input could still become a
+// valid literal or number once more input arrives
+fn is_truncated(token: &str) -> bool {
+    ["true", "false", "null"]
+        .iter()
+        .any(|lit| lit.len() > token.len() && lit.starts_with(token))
+        || token.ends_with(['-', '+', '.', 'e', 'E'])
+}
+
+impl Iterator for StreamDeserializer<'_> {
+    type Item = Result<JSON, JSONError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.mode {
+            Mode::Concatenated => {
+                let result = self.next_value();
+                if !matches!(result, Some(Ok(_))) {
+                    self.finished = true;
+                }
+                result
+            }
+            Mode::Sequence => loop {
+                let record_start = self.base;
+                let result = match self.next_value() {
+                    // Each record holds exactly one value
+                    Some(Ok(value)) => match self.tokens.next() {
+                        None => Some(Ok(value)),
+                        Some(Err(e)) => Some(Err(e)),
+                        Some(Ok(_)) => Some(Err(JSONError::ParseError(
+                            ErrorKind::MultipleValues,
+                            self.tokens.position(),
+                        ))),
+                    },
+                    other => other,
+                };
+                if !self.next_record(record_start) {
+                    self.finished = true;
+                }
+                match result {
+                    Some(result) => return Some(result),
+                    None if self.finished => return None,
+                    None => continue,
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::borrow::Cow;
+
+use super::{JSONError, Position};
+
+// Decodes the contents of a string token (without its surrounding quotes).
+// `at` is the position of the first byte of `raw`. Strings without
+// escapes are borrowed as is.
+pub(crate) fn decode_string(raw: &str, at: Position) -> Result<Cow<'_, str>, JSONError> {
+    let bytes = raw.as_bytes();
+    if let Some(i) = bytes.iter().position(|b| *b < 0x20) {
+        if !bytes[..i].contains(&b'\\') {
+            return Err(JSONError::UnexpectedCharacter(
+                bytes[i] as char,
+                at.advance(i),
+            ));
+        }
+    } else if !bytes.contains(&b'\\') {
+        return Ok(Cow::Borrowed(raw));
+    }
+
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c < '\x20' {
+            return Err(JSONError::UnexpectedCharacter(c, at.advance(i)));
+        }
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        let esc = match chars.next() {
+            Some((_, e)) => e,
+            None => return Err(JSONError::InvalidEscape('\\', at.advance(i))),
+        };
+        match esc {
+            '"' => out.push('"'),
+            '\\' => out.push('\\'),
+            '/' => out.push('/'),
+            'b' => out.push('\x08'),
+            'f' => out.push('\x0c'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'u' => {
+                let (c, len) = decode_unicode_escape(raw, i, at.advance(i))?;
+                out.push(c);
+                chars.nth(len - 3);
+            }
+            e => return Err(JSONError::InvalidEscape(e, at.advance(i))),
+        }
+    }
+    Ok(Cow::Owned(out))
+}
+
+// Decodes the `\uXXXX` escape at `raw[i]`, which is at `at`, together with
+// the low surrogate escape that has to follow a high one. Returns the character
+// and the length of the escapes.
+pub(crate) fn decode_unicode_escape(
+    raw: &str,
+    i: usize,
+    at: Position,
+) -> Result<(char, usize), JSONError> {
+    let high = read_hex(raw, i + 2, 4, at, 'u')?;
+    let (code, len) = match high {
+        0xD800..=0xDBFF => {
+            if !raw[i + 6..].starts_with("\\u") {
+                return Err(JSONError::LoneSurrogate(high as u16, at));
+            }
+            let low = read_hex(raw, i + 8, 4, at.advance(6), 'u')?;
+            if !(0xDC00..=0xDFFF).contains(&low) {
+                return Err(JSONError::LoneSurrogate(high as u16, at));
+            }
+            (0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00), 12)
+        }
+        0xDC00..=0xDFFF => return Err(JSONError::LoneSurrogate(high as u16, at)),
+        _ => (high, 6),
+    };
+    Ok((char::from_u32(code).expect("Surrogates handled above"), len))
+}
+
+// Reads the `len` hex digits of the escape starting at `raw[start - 2]`, which
+// is at `at`
+pub(crate) fn read_hex(
+    raw: &str,
+    start: usize,
+    len: usize,
+    at: Position,
+    esc: char,
+) -> Result<u32, JSONError> {
+    let bytes = raw.as_bytes();
+    let mut value = 0;
+    for offset in start..start + len {
+        let b = match bytes.get(offset) {
+            Some(b) => *b,
+            None => return Err(JSONError::InvalidEscape(esc, at)),
+        };
+        match (b as char).to_digit(16) {
+            Some(d) => value = value * 16 + d,
+            None => {
+                let c = raw[offset..].chars().next().expect("In bounds");
+                return Err(JSONError::UnexpectedCharacter(
+                    c,
+                    at.advance(offset + 2 - start),
+                ));
+            }
+        }
+    }
+    Ok(value)
+}
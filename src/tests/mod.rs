@@ -1,4 +1,12 @@
-use crate::json::JSON;
+use crate::json::{
+    patch::Operation, Change, DiffConfig, Indent, JSONPath, JSONWriter, JSONWriterError, Map,
+    NDJSONWriter, Newline, NonFinite, Patch, PatchError, PathError, PointerError, PrettyConfig,
+    JSON,
+};
+use crate::parsing::{
+    Comment, DuplicateKeys, ErrorKind, Event, EventReader, JSONError, NDJSONIter, NDJSONReader,
+    ParseOptions, Position, StreamDeserializer, DEFAULT_MAX_DEPTH,
+};
 use std::str::FromStr;
 use std::time::Instant;
 
@@ -6,7 +14,7 @@ use std::time::Instant;
 #[test]
 fn test_arr() {
     let obj1 = JSON::String("hello".to_string());
-    let obj2 = JSON::Number(42.0);
+    let obj2 = JSON::Number(42.0.into());
     let obj3 = JSON::Bool(true);
     let arr = JSON::Array(vec![obj1, obj2, obj3]);
     assert_eq!(arr.to_string(), "[\"hello\", 42, true]");
@@ -207,3 +215,1904 @@ fn large_complex() {
         assert!(actor.get("login").is_some());
     }
 }
+
+#[test]
+fn test_string_escapes() {
+    let str = r#"{"a\nb": "tab\there \"quoted\" \\ \/ é 😀"}"#;
+    let obj = JSON::from_str(str).unwrap();
+    match obj.get("a\nb") {
+        Some(JSON::String(s)) => assert_eq!(s, "tab\there \"quoted\" \\ / é 😀"),
+        _ => panic!("escaped key not decoded"),
+    }
+
+    match JSON::from_str(r#"["ok", "bad \q"]"#) {
+        Err(JSONError::InvalidEscape(
+            'q',
+            Position {
+                line: 1,
+                column: 13,
+                ..
+            },
+        )) => (),
+        other => panic!("expected invalid escape, got {:?}", other),
+    }
+    match JSON::from_str(r#"["\ud83d"]"#) {
+        Err(JSONError::LoneSurrogate(
+            0xD83D,
+            Position {
+                line: 1, column: 3, ..
+            },
+        )) => (),
+        other => panic!("expected lone surrogate, got {:?}", other),
+    }
+    match JSON::from_str(r#"["\ude00\ud83d"]"#) {
+        Err(JSONError::LoneSurrogate(
+            0xDE00,
+            Position {
+                line: 1, column: 3, ..
+            },
+        )) => (),
+        other => panic!("expected lone surrogate, got {:?}", other),
+    }
+    assert!(JSON::from_str("[\"raw\tcontrol\"]").is_err());
+    assert!(JSON::from_str(r#"["\u12G4"]"#).is_err());
+}
+
+#[test]
+fn test_string_escaping_round_trip() {
+    let mut map = std::collections::HashMap::new();
+    map.insert("quote\"key", "back\\slash\n\ttab \u{1} \u{1f} é 😀");
+    let obj = JSON::from(map);
+    assert_eq!(
+        obj.to_string(),
+        r#"{"quote\"key": "back\\slash\n\ttab \u0001 \u001f é 😀"}"#
+    );
+    assert_eq!(JSON::from_str(&obj.to_string()).unwrap(), obj);
+
+    let ascii = obj.to_ascii_string();
+    assert!(ascii.is_ascii());
+    assert!(ascii.ends_with(r#"\u00e9 \ud83d\ude00"}"#));
+    assert_eq!(JSON::from_str(&ascii).unwrap(), obj);
+}
+
+#[test]
+fn test_strict_numbers() {
+    let arr = JSON::from_str("[0, -0, 1.5, -12e3, 4E+2, 5e-1 ]").unwrap();
+    let nums: Vec<f64> = arr
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_f64().unwrap())
+        .collect();
+    assert_eq!(nums, vec![0.0, -0.0, 1.5, -12000.0, 400.0, 0.5]);
+    assert_eq!(JSON::from_str("\r\n42\r\n").unwrap(), JSON::from(42));
+
+    for (bad, c, pos) in [
+        ("[inf]", 'i', 2),
+        ("[NaN]", 'N', 2),
+        ("[+1]", '+', 2),
+        ("[01]", '1', 3),
+        ("[.5]", '.', 2),
+        ("[1.]", '.', 3),
+        ("[1e]", 'e', 3),
+        ("[-]", '-', 2),
+        ("[hello]", 'h', 2),
+        ("[tru\"e\"]", 't', 2),
+    ] {
+        match JSON::from_str(bad) {
+            Err(JSONError::UnexpectedCharacter(
+                ch,
+                Position {
+                    line: 1, column: p, ..
+                },
+            )) => {
+                assert_eq!((ch, p), (c, pos), "{}", bad)
+            }
+            other => panic!("{} parsed as {:?}", bad, other),
+        }
+    }
+}
+
+#[test]
+fn test_lossless_integers() {
+    let str = r#"[9007199254740993, -9223372036854775808, 18446744073709551615, 1.5, -0]"#;
+    let arr = JSON::from_str(str).unwrap();
+    assert_eq!(arr.at(0).unwrap().as_u64(), Some(9_007_199_254_740_993));
+    assert_eq!(arr.at(0).unwrap().as_i64(), Some(9_007_199_254_740_993));
+    assert_eq!(arr.at(1).unwrap().as_i64(), Some(i64::MIN));
+    assert_eq!(arr.at(1).unwrap().as_u64(), None);
+    assert_eq!(arr.at(2).unwrap().as_u64(), Some(u64::MAX));
+    assert_eq!(arr.at(3).unwrap().as_i64(), None);
+    assert_eq!(arr.at(3).unwrap().as_f64(), Some(1.5));
+    assert_eq!(
+        arr.to_string(),
+        "[9007199254740993, -9223372036854775808, 18446744073709551615, 1.5, -0]"
+    );
+
+    assert_eq!(JSON::from(1u64), JSON::from(1.0));
+    assert_ne!(
+        JSON::from(9_007_199_254_740_993u64),
+        JSON::from(9_007_199_254_740_992.0)
+    );
+    // Floats past 2^53 are written so that they read back as the same float
+    for f in [-5.34574943530375e16, 9_007_199_254_740_992.0, 1e20] {
+        let value = JSON::from(f);
+        assert_eq!(
+            JSON::from_str(&value.to_string()).unwrap(),
+            value,
+            "{}",
+            value
+        );
+    }
+
+    // Too large for an f64 is an error rather than infinity, unless the text
+    // is kept as it is
+    for too_large in ["[1, 1e400]", "[1, -1.5e309]"] {
+        let parsed = JSON::from_str(too_large);
+        #[cfg(not(feature = "arbitrary_precision"))]
+        match parsed {
+            Err(JSONError::ParseError(ErrorKind::NumberOutOfRange, p)) => {
+                assert_eq!(p.column, 5, "{}", too_large)
+            }
+            other => panic!("{} parsed as {:?}", too_large, other),
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        assert_eq!(parsed.unwrap().to_string(), too_large);
+    }
+    #[cfg(not(feature = "arbitrary_precision"))]
+    {
+        assert_eq!(
+            "1e400".parse::<crate::json::Number>().unwrap_err().kind(),
+            ErrorKind::NumberOutOfRange
+        );
+        assert_eq!(JSON::from_str("1e-400").unwrap(), JSON::from(0));
+        assert_eq!(
+            JSON::from_json5("+1e400").unwrap_err().kind(),
+            ErrorKind::NumberOutOfRange
+        );
+    }
+    let hex = format!("0x{}", "f".repeat(300));
+    assert_eq!(
+        JSON::from_json5(&hex).unwrap_err().kind(),
+        ErrorKind::NumberOutOfRange
+    );
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_arbitrary_precision() {
+    let str = "[123456789012345678901234567890, 1.000000000000000000001, 1.0e2]";
+    let arr = JSON::from_str(str).unwrap();
+    assert_eq!(
+        arr.to_string(),
+        "[123456789012345678901234567890, 1.000000000000000000001, 1.0e2]"
+    );
+    assert_eq!(
+        arr.at(0).unwrap().as_number().unwrap().as_str(),
+        Some("123456789012345678901234567890")
+    );
+    assert_eq!(arr.at(2).unwrap().as_f64(), Some(100.0));
+    assert_eq!(*arr.at(2).unwrap(), JSON::from(100));
+
+    // Text is compared exactly, not after rounding to f64
+    let parse = |s: &str| JSON::from_str(s).unwrap();
+    let (a, b) = (
+        parse("123456789012345678901234567890"),
+        parse("123456789012345678901234567891"),
+    );
+    assert_ne!(a, b);
+    assert!(a.as_number() < b.as_number());
+    assert_eq!(a.diff(&b).len(), 1);
+    assert_eq!(Patch::diff(&a, &b).0.len(), 1);
+    assert_ne!(parse("1.000000000000000000001"), JSON::from(1));
+    assert_ne!(parse("1.000000000000000000001"), parse("1.0"));
+    for (x, y) in [
+        ("1.0e2", "100"),
+        ("0.00123e3", "1.23"),
+        ("-0.0", "0e10"),
+        ("1e400", "10e399"),
+    ] {
+        assert_eq!(parse(x), parse(y), "{} == {}", x, y);
+    }
+    for (x, y) in [("-1e400", "-1e399"), ("-2", "-1.5"), ("1e-400", "1e-399")] {
+        assert!(parse(x).as_number() < parse(y).as_number(), "{} < {}", x, y);
+    }
+    // Against a float the text is rounded
+    assert_eq!(parse("0.1000000000000000000001"), JSON::from(0.1));
+}
+
+#[test]
+fn test_structural_errors() {
+    assert_eq!(JSON::from_str("[]").unwrap(), JSON::Array(vec![]));
+    assert_eq!(JSON::from_str(" \"top\" ").unwrap(), JSON::from("top"));
+
+    for (bad, msg) in [
+        ("[1 2]", "Unexpected number"),
+        ("[1,]", "Trailing comma in array"),
+        (r#"{"a": 1,}"#, "Trailing comma in object"),
+        (r#"{"a" 1}"#, "Unexpected number"),
+        (r#"{1: 1}"#, "Non string used as object key"),
+        (r#"{"a": 1]"#, "Unexpected end square brace"),
+        (r#"[1}"#, "Unexpected end curly brace"),
+        (r#"[1, :]"#, "Unexpected colon"),
+        (
+            r#"{"a": 1} {"b": 2}"#,
+            "Multiple independent JSON objects present",
+        ),
+    ] {
+        match JSON::from_str(bad) {
+            Err(JSONError::ParseError(kind, _)) => assert_eq!(kind.to_string(), msg, "{}", bad),
+            other => panic!("{} parsed as {:?}", bad, other),
+        }
+    }
+
+    for bad in ["", "  ", "[1, 2", r#"{"a": "#] {
+        assert!(matches!(
+            JSON::from_str(bad),
+            Err(JSONError::UnexpectedEndOfInput(_))
+        ));
+    }
+}
+
+#[test]
+fn test_object_order() {
+    let str = r#"{"zebra": 1, "apple": 2, "mango": {"y": true, "b": null}, "kiwi": []}"#;
+    let mut obj = JSON::from_str(str).unwrap();
+    assert_eq!(
+        obj.to_string(),
+        r#"{"zebra": 1, "apple": 2, "mango": {"y": true, "b": null}, "kiwi": []}"#
+    );
+
+    obj.insert("apple".to_string(), JSON::from(3)).ok();
+    obj.insert("banana".to_string(), JSON::Null).ok();
+    let keys: Vec<&String> = obj.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["zebra", "apple", "mango", "kiwi", "banana"]);
+    assert_eq!(obj.get("apple"), Some(&JSON::from(3)));
+
+    let mut map = Map::new();
+    for i in (0..40).rev() {
+        map.insert(format!("k{}", i), JSON::from(i));
+    }
+    map.remove("k20");
+    assert_eq!(map.len(), 39);
+    assert_eq!(map.get("k19"), Some(&JSON::from(19)));
+    assert_eq!(map.keys().nth(20).unwrap(), "k18");
+    let mut removed = map.clone();
+    for i in (0..40).step_by(3) {
+        removed.remove(&format!("k{}", i));
+    }
+    for i in 0..40 {
+        let expected = (i % 3 != 0 && i != 20).then(|| JSON::from(i));
+        assert_eq!(removed.get(&format!("k{}", i)), expected.as_ref());
+    }
+    assert!(removed
+        .keys()
+        .eq(map.keys().filter(|k| removed.contains_key(k))));
+    assert_eq!(
+        JSON::from(map.clone()),
+        JSON::from_str(&JSON::from(map).to_string()).unwrap()
+    );
+}
+
+#[test]
+fn test_pretty_print() {
+    let obj = JSON::from_str(r#"{"b": [1, {"c": null}], "a": "x", "e": [], "d": {}}"#).unwrap();
+
+    assert_eq!(
+        format!("{:#}", obj),
+        "{\n  \"b\": [\n    1,\n    {\n      \"c\": null\n    }\n  ],\n  \"a\": \"x\",\n  \"e\": [],\n  \"d\": {}\n}"
+    );
+    assert_eq!(
+        obj.to_string_pretty(&PrettyConfig::compact()).unwrap(),
+        r#"{"b":[1,{"c":null}],"a":"x","e":[],"d":{}}"#
+    );
+
+    let config = PrettyConfig {
+        indent: Some(Indent::Tabs(1)),
+        newline: Newline::CrLf,
+        sort_keys: true,
+        ..PrettyConfig::new()
+    };
+    assert_eq!(
+        obj.to_string_pretty(&config).unwrap(),
+        "{\r\n\t\"a\": \"x\",\r\n\t\"b\": [\r\n\t\t1,\r\n\t\t{\r\n\t\t\t\"c\": null\r\n\t\t}\r\n\t],\r\n\t\"d\": {},\r\n\t\"e\": []\r\n}"
+    );
+
+    let config = PrettyConfig {
+        indent: Some(Indent::Spaces(4)),
+        compact: true,
+        ..PrettyConfig::new()
+    };
+    assert_eq!(
+        obj.get("b").unwrap().to_string_pretty(&config).unwrap(),
+        "[\n    1,\n    {\n        \"c\":null\n    }\n]"
+    );
+    assert_eq!(JSON::from_str(&format!("{:#}", obj)).unwrap(), obj);
+}
+
+#[test]
+fn test_streaming_writer() {
+    let obj = JSON::from_str(
+        r#"{"users": [{"id": 1, "tags": []}, {"id": 2, "tags": ["a"]}], "ok": true}"#,
+    )
+    .unwrap();
+
+    let mut buf = Vec::new();
+    obj.write_to(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), obj.to_string());
+
+    for config in [PrettyConfig::compact(), PrettyConfig::new()] {
+        let mut w = JSONWriter::with_config(Vec::new(), config.clone());
+        w.begin_object().unwrap();
+        w.key("users").unwrap();
+        w.begin_array().unwrap();
+        for id in 1..=2 {
+            w.begin_object().unwrap();
+            w.key("id").unwrap();
+            w.number(id).unwrap();
+            w.key("tags").unwrap();
+            w.begin_array().unwrap();
+            if id == 2 {
+                w.string("a").unwrap();
+            }
+            w.end_array().unwrap();
+            w.end_object().unwrap();
+        }
+        w.end_array().unwrap();
+        w.key("ok").unwrap();
+        w.value(&JSON::Bool(true)).unwrap();
+        w.end_object().unwrap();
+        let out = String::from_utf8(w.finish().unwrap()).unwrap();
+        assert_eq!(out, obj.to_string_pretty(&config).unwrap());
+    }
+
+    let mut w = JSONWriter::new(Vec::new());
+    w.begin_object().unwrap();
+    assert!(matches!(w.null(), Err(JSONWriterError::InvalidState(_))));
+    assert!(matches!(
+        w.end_array(),
+        Err(JSONWriterError::InvalidState(_))
+    ));
+    w.key("a").unwrap();
+    assert!(matches!(w.key("b"), Err(JSONWriterError::InvalidState(_))));
+    assert!(matches!(
+        w.end_object(),
+        Err(JSONWriterError::InvalidState(_))
+    ));
+    w.null().unwrap();
+    w.end_object().unwrap();
+    assert!(matches!(w.null(), Err(JSONWriterError::InvalidState(_))));
+    assert_eq!(w.finish().unwrap(), br#"{"a":null}"#);
+
+    let mut w = JSONWriter::new(Vec::new());
+    w.begin_array().unwrap();
+    assert!(matches!(w.finish(), Err(JSONWriterError::InvalidState(_))));
+}
+
+struct TrickleReader<'a> {
+    data: &'a [u8],
+    step: usize,
+}
+
+impl std::io::Read for TrickleReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.step.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_from_reader() {
+    let long = "x".repeat(20_000);
+    let str = format!(
+        "{{\"a\\\"b\": [true, false, null, -12.5e3, 18446744073709551615],\r\n \"long\": \"{}\", \"é\": \"\\ud83d\\ude00\"}}",
+        long
+    );
+    let expected = JSON::from_str(&str).unwrap();
+    for step in [1, 3, 7, 8192, usize::MAX] {
+        let reader = TrickleReader {
+            data: str.as_bytes(),
+            step,
+        };
+        assert_eq!(JSON::from_reader(reader).unwrap(), expected);
+    }
+
+    let bad = TrickleReader {
+        data: b"[1,\n 2,\n tru]",
+        step: 2,
+    };
+    match JSON::from_reader(bad) {
+        Err(JSONError::UnexpectedCharacter(
+            't',
+            Position {
+                line: 3, column: 2, ..
+            },
+        )) => (),
+        other => panic!("expected bad literal, got {:?}", other),
+    }
+    assert!(matches!(
+        JSON::from_reader(&b"[\"unterminated"[..]),
+        Err(JSONError::UnexpectedEndOfInput(_))
+    ));
+    assert!(matches!(
+        JSON::from_reader(&b"[\"\xff\"]"[..]),
+        Err(JSONError::ParseError(..))
+    ));
+}
+
+#[test]
+fn test_event_reader() {
+    let str = "{\"users\": [\n  {\"name\": \"Ann\", \"tags\": [\"a\\nb\"]},\n  {\"name\": \"Bob\", \"age\": 42, \"ok\": null}\n], \"flag\": true}";
+    let events: Vec<(Event, Position)> = EventReader::new(str).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 23);
+    assert_eq!(
+        events[0],
+        (
+            Event::StartObject,
+            Position {
+                line: 1,
+                column: 1,
+                offset: 0
+            }
+        )
+    );
+    assert_eq!(
+        events[1],
+        (
+            Event::Key("users".into()),
+            Position {
+                line: 1,
+                column: 2,
+                offset: 1
+            }
+        )
+    );
+    assert_eq!(
+        events[4],
+        (
+            Event::Key("name".into()),
+            Position {
+                line: 2,
+                column: 4,
+                offset: 15
+            }
+        )
+    );
+    assert_eq!(events[8].0, Event::String("a\nb".into()));
+    assert!(matches!(
+        events[8].0,
+        Event::String(std::borrow::Cow::Owned(_))
+    ));
+    assert_eq!(events[15].0, Event::Number(42.into()));
+    assert_eq!(events[22].0, Event::EndObject);
+
+    let mut names = Vec::new();
+    let mut reader = EventReader::new(str);
+    while let Some(event) = reader.next() {
+        match event.unwrap().0 {
+            Event::Key(k) if k == "name" => {
+                names.push(reader.read_value().unwrap().unwrap());
+            }
+            Event::Key(k) if k == "tags" => reader.skip_value().unwrap(),
+            _ => (),
+        }
+    }
+    assert_eq!(names, vec![JSON::from("Ann"), JSON::from("Bob")]);
+
+    let mut bad = EventReader::new("[1, 2 3]");
+    assert!(bad.next().unwrap().is_ok());
+    assert!(bad.next().unwrap().is_ok());
+    assert!(bad.next().unwrap().is_ok());
+    assert!(matches!(bad.next(), Some(Err(JSONError::ParseError(..)))));
+    assert!(bad.next().is_none());
+
+    let mut truncated = EventReader::new("[1");
+    assert!(truncated.next().unwrap().is_ok());
+    assert!(truncated.next().unwrap().is_ok());
+    assert!(matches!(
+        truncated.next(),
+        Some(Err(JSONError::UnexpectedEndOfInput(_)))
+    ));
+}
+
+#[test]
+fn test_ndjson() {
+    let str = "{\"id\": 1}\r\n[1, 2]\n\n   \n{\"id\": tru}\n\"last\"";
+    let results: Vec<Result<JSON, JSONError>> = NDJSONIter::new(str).collect();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].as_ref().unwrap().get("id"), Some(&JSON::from(1)));
+    assert_eq!(results[1].as_ref().unwrap().as_array().unwrap().len(), 2);
+    match &results[2] {
+        Err(JSONError::UnexpectedCharacter(
+            't',
+            Position {
+                line: 5, column: 8, ..
+            },
+        )) => (),
+        other => panic!("expected error on line 5, got {:?}", other),
+    }
+    assert_eq!(results[3].as_ref().unwrap(), &JSON::from("last"));
+
+    let skipped: Vec<JSON> = NDJSONIter::new(str)
+        .skip_invalid(true)
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(skipped.len(), 3);
+
+    let from_reader: Vec<JSON> = NDJSONReader::new(TrickleReader {
+        data: str.as_bytes(),
+        step: 3,
+    })
+    .skip_invalid(true)
+    .map(|r| r.unwrap())
+    .collect();
+    assert_eq!(from_reader, skipped);
+
+    let mut reader = NDJSONReader::new(&b"1\n{\n\xff\n2"[..]);
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(JSONError::UnexpectedEndOfInput(_)))
+    ));
+    assert_eq!(reader.line_number(), 2);
+    assert!(reader.next().unwrap().is_err());
+    assert_eq!(reader.next().unwrap().unwrap(), JSON::from(2));
+    assert!(reader.next().is_none());
+
+    let mut writer = NDJSONWriter::new(Vec::new());
+    writer.write_all(&skipped).unwrap();
+    let out = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(out, "{\"id\":1}\n[1,2]\n\"last\"\n");
+    let reparsed: Vec<JSON> = NDJSONIter::new(&out).map(|r| r.unwrap()).collect();
+    assert_eq!(reparsed, skipped);
+}
+
+#[test]
+fn test_stream_deserializer() {
+    let str = "{\"a\": 1}{\"b\": [2]}[3] 4\n\"five\"null";
+    let mut stream = StreamDeserializer::new(str);
+    let mut offsets = Vec::new();
+    let mut values = Vec::new();
+    while let Some(value) = stream.next() {
+        values.push(value.unwrap());
+        offsets.push(stream.byte_offset());
+    }
+    assert_eq!(values.len(), 6);
+    assert_eq!(values[3], JSON::from(4));
+    assert_eq!(values[5], JSON::Null);
+    assert_eq!(offsets, vec![8, 18, 21, 23, 30, 34]);
+
+    let partial = "[1, 2] {\"a\": [tr";
+    let mut stream = StreamDeserializer::new(partial);
+    assert_eq!(stream.next().unwrap().unwrap().as_array().unwrap().len(), 2);
+    assert!(matches!(
+        stream.next(),
+        Some(Err(JSONError::UnexpectedEndOfInput(_)))
+    ));
+    assert!(stream.next().is_none());
+    let resumed = format!("{}ue]}}", &partial[stream.byte_offset()..]);
+    let value = StreamDeserializer::new(&resumed).next().unwrap().unwrap();
+    assert_eq!(value.get("a").unwrap().at(0), Some(&JSON::Bool(true)));
+
+    let seq = "\x1e{\"a\": 1}\n\x1e[1, }\n\x1e2 3\n\x1e\"x\"\n\x1e\n\x1e  tru\n";
+    let results: Vec<Result<JSON, JSONError>> = StreamDeserializer::json_seq(seq).collect();
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0].as_ref().unwrap().get("a"), Some(&JSON::from(1)));
+    assert!(matches!(results[1], Err(JSONError::ParseError(..))));
+    assert!(matches!(results[2], Err(JSONError::ParseError(..))));
+    assert_eq!(results[3].as_ref().unwrap(), &JSON::from("x"));
+    match &results[4] {
+        Err(JSONError::UnexpectedCharacter(
+            't',
+            Position {
+                line: 6, column: 4, ..
+            },
+        )) => (),
+        other => panic!("expected error on line 6, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_json_pointer() {
+    // Examples from RFC 6901 section 5
+    let doc = JSON::from_str(
+        r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3, "g|h": 4, "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8}"#,
+    )
+    .unwrap();
+    assert_eq!(doc.pointer("").unwrap(), &doc);
+    assert_eq!(doc.pointer("/foo").unwrap().as_array().unwrap().len(), 2);
+    assert_eq!(doc.pointer("/foo/0").unwrap(), &JSON::from("bar"));
+    for (ptr, n) in [
+        ("/", 0),
+        ("/a~1b", 1),
+        ("/c%d", 2),
+        ("/e^f", 3),
+        ("/g|h", 4),
+        ("/i\\j", 5),
+        ("/k\"l", 6),
+        ("/ ", 7),
+        ("/m~0n", 8),
+    ] {
+        assert_eq!(doc.pointer(ptr).unwrap(), &JSON::from(n), "{}", ptr);
+    }
+
+    assert_eq!(
+        doc.pointer("/foo/2"),
+        Err(PointerError::IndexOutOfBounds(1, 2, 2))
+    );
+    assert_eq!(
+        doc.pointer("/foo/01"),
+        Err(PointerError::InvalidIndex(1, "01".to_string()))
+    );
+    assert_eq!(
+        doc.pointer("/foo/-"),
+        Err(PointerError::InvalidIndex(1, "-".to_string()))
+    );
+    assert_eq!(
+        doc.pointer("/missing/x"),
+        Err(PointerError::KeyNotFound(0, "missing".to_string()))
+    );
+    assert_eq!(
+        doc.pointer("/a~1b/x"),
+        Err(PointerError::NotAContainer(1, "x".to_string()))
+    );
+    assert!(matches!(
+        doc.pointer("foo"),
+        Err(PointerError::InvalidPointer(_))
+    ));
+    assert!(matches!(
+        doc.pointer("/m~2n"),
+        Err(PointerError::InvalidPointer(_))
+    ));
+
+    let mut doc = doc;
+    *doc.pointer_mut("/foo/1").unwrap() = JSON::from("qux");
+    doc.insert_at("/foo/0", JSON::from("first"), false).unwrap();
+    doc.insert_at("/foo/-", JSON::from("last"), false).unwrap();
+    assert_eq!(
+        doc.get("foo").unwrap().to_string(),
+        r#"["first", "bar", "qux", "last"]"#
+    );
+    assert_eq!(
+        doc.insert_at("/x/y/0/z", JSON::Null, false),
+        Err(PointerError::KeyNotFound(0, "x".to_string()))
+    );
+    doc.insert_at("/x/y/-/z", JSON::Null, true).unwrap();
+    doc.insert_at("/x/y/-/z", JSON::from(1), true).unwrap();
+    assert_eq!(
+        doc.get("x").unwrap().to_string(),
+        r#"{"y": [{"z": null}, {"z": 1}]}"#
+    );
+
+    assert_eq!(doc.remove_at("/foo/0").unwrap(), JSON::from("first"));
+    assert_eq!(doc.remove_at("/m~0n").unwrap(), JSON::from(8));
+    assert_eq!(
+        doc.remove_at("/foo/3"),
+        Err(PointerError::IndexOutOfBounds(1, 3, 3))
+    );
+    assert!(doc.get("m~n").is_none());
+}
+
+#[test]
+fn test_json_path() {
+    // The example document from RFC 9535 section 1.5
+    let store = JSON::from_str(
+        r#"{ "store": {
+            "book": [
+              { "category": "reference", "author": "Nigel Rees",
+                "title": "Sayings of the Century", "price": 8.95 },
+              { "category": "fiction", "author": "Evelyn Waugh",
+                "title": "Sword of Honour", "price": 12.99 },
+              { "category": "fiction", "author": "Herman Melville",
+                "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99 },
+              { "category": "fiction", "author": "J. R. R. Tolkien",
+                "title": "The Lord of the Rings", "isbn": "0-395-19395-8",
+                "price": 22.99 }
+            ],
+            "bicycle": { "color": "red", "price": 399 }
+        } }"#,
+    )
+    .unwrap();
+    let paths = |query: &str| -> Vec<String> {
+        store
+            .query(query)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.path)
+            .collect()
+    };
+    let values = |query: &str| -> Vec<String> {
+        let path: JSONPath = query.parse().unwrap();
+        path.values(&store).iter().map(|v| v.to_string()).collect()
+    };
+
+    assert_eq!(
+        values("$.store.book[*].author"),
+        [
+            "\"Nigel Rees\"",
+            "\"Evelyn Waugh\"",
+            "\"Herman Melville\"",
+            "\"J. R. R. Tolkien\""
+        ]
+    );
+    assert_eq!(values("$..author").len(), 4);
+    assert_eq!(values("$.store.*").len(), 2);
+    assert_eq!(
+        paths("$.store..price"),
+        [
+            "$['store']['book'][0]['price']",
+            "$['store']['book'][1]['price']",
+            "$['store']['book'][2]['price']",
+            "$['store']['book'][3]['price']",
+            "$['store']['bicycle']['price']"
+        ]
+    );
+    assert_eq!(paths("$..book[2]"), ["$['store']['book'][2]"]);
+    assert_eq!(paths("$..book[-1]"), ["$['store']['book'][3]"]);
+    assert_eq!(
+        paths("$..book[0,1]"),
+        ["$['store']['book'][0]", "$['store']['book'][1]"]
+    );
+    assert_eq!(paths("$..book[:2]"), paths("$..book[0,1]"));
+    assert_eq!(
+        values("$..book[?@.isbn].title"),
+        ["\"Moby Dick\"", "\"The Lord of the Rings\""]
+    );
+    assert_eq!(
+        values("$..book[?@.price<10].title"),
+        ["\"Sayings of the Century\"", "\"Moby Dick\""]
+    );
+    assert_eq!(
+        values("$..book[?(@.category == 'fiction' && !(@.price >= 20))].title"),
+        ["\"Sword of Honour\"", "\"Moby Dick\""]
+    );
+    assert_eq!(
+        values("$..book[?@.author == \"Nigel Rees\" || @.price > 20].price"),
+        ["8.95", "22.99"]
+    );
+    assert_eq!(
+        values("$..book[?@.price == $.store.bicycle.price]").len(),
+        0
+    );
+    assert_eq!(values("$..*").len(), 27);
+    assert_eq!(values("$.store.missing").len(), 0);
+
+    let array = JSON::from_str(r#"["a", "b", "c", "d", "e", "f", "g"]"#).unwrap();
+    let slice = |query: &str| -> String {
+        let path: JSONPath = query.parse().unwrap();
+        path.values(&array)
+            .iter()
+            .map(|v| v.as_string().unwrap())
+            .collect()
+    };
+    assert_eq!(slice("$[1:3]"), "bc");
+    assert_eq!(slice("$[5:]"), "fg");
+    assert_eq!(slice("$[1:5:2]"), "bd");
+    assert_eq!(slice("$[5:1:-2]"), "fd");
+    assert_eq!(slice("$[::-1]"), "gfedcba");
+    assert_eq!(slice("$[-2:]"), "fg");
+    assert_eq!(slice("$[::0]"), "");
+    assert_eq!(slice("$[0, 0, -1]"), "aag");
+
+    let users = JSON::from_str(
+        r#"{"users": [
+            {"name": "Ann", "friends": [{"name": "Bo", "hobbies": ["chess"]}, {"name": "Cy"}]},
+            {"name": "Di", "friends": [{"name": "Ed", "hobbies": []}]}
+        ]}"#,
+    )
+    .unwrap();
+    let matches = users
+        .query("$.users[*].friends[?(@.hobbies)].name")
+        .unwrap();
+    let found: Vec<(&str, &str)> = matches
+        .iter()
+        .map(|m| (m.path.as_str(), m.value.as_string().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("$['users'][0]['friends'][0]['name']", "Bo"),
+            ("$['users'][1]['friends'][0]['name']", "Ed")
+        ]
+    );
+
+    let odd_keys = JSON::from_str(r#"{"it's": {"a\\b\n": 1}}"#).unwrap();
+    assert_eq!(
+        odd_keys.query("$[\"it's\"].*").unwrap()[0].path,
+        "$['it\\'s']['a\\\\b\\n']"
+    );
+
+    let emoji = JSON::from_str(r#"{"\ud83d\ude00": 1}"#).unwrap();
+    assert_eq!(emoji.query(r"$['\ud83d\ude00']").unwrap().len(), 1);
+
+    for (query, error) in [
+        (
+            r"$['\ud83d']",
+            PathError::InvalidSyntax("Unpaired surrogate", 3),
+        ),
+        (r"$['\u12x4']", PathError::UnexpectedCharacter('x', 7)),
+        (r"$['\u12", PathError::UnexpectedEndOfInput),
+        ("store", PathError::UnexpectedCharacter('s', 0)),
+        ("$.", PathError::UnexpectedEndOfInput),
+        ("$[01]", PathError::InvalidSyntax("Invalid integer", 2)),
+        ("$[-0]", PathError::InvalidSyntax("Invalid integer", 2)),
+        ("$[?@.a == 'x]", PathError::UnexpectedEndOfInput),
+        ("$.a ", PathError::UnexpectedCharacter(' ', 3)),
+        (
+            "$[?@.* == 1]",
+            PathError::InvalidSyntax(
+                "Comparison with a query that can select more than one value",
+                3,
+            ),
+        ),
+        (
+            "$[?length(@) == 1]",
+            PathError::InvalidSyntax("Function expressions are not supported", 3),
+        ),
+        (
+            "$[?true]",
+            PathError::InvalidSyntax("Literal used without a comparison", 3),
+        ),
+    ] {
+        assert_eq!(query.parse::<JSONPath>(), Err(error), "{}", query);
+    }
+}
+
+#[test]
+fn test_json_patch() {
+    let patch_of = |s: &str| Patch::from_json(&JSON::from_str(s).unwrap()).unwrap();
+    let apply = |doc: &str, patch: &str| -> Result<String, PatchError> {
+        let mut doc = JSON::from_str(doc).unwrap();
+        patch_of(patch).apply(&mut doc)?;
+        Ok(doc.to_string())
+    };
+
+    // Examples from RFC 6902 appendix A
+    assert_eq!(
+        apply(
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#
+        )
+        .unwrap(),
+        r#"{"foo": "bar", "baz": "qux"}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"foo": ["bar", "baz"]}"#,
+            r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#
+        )
+        .unwrap(),
+        r#"{"foo": ["bar", "qux", "baz"]}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "remove", "path": "/baz"}]"#
+        )
+        .unwrap(),
+        r#"{"foo": "bar"}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#
+        )
+        .unwrap(),
+        r#"{"baz": "boo", "foo": "bar"}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+            r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#
+        )
+        .unwrap(),
+        r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+            r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#
+        )
+        .unwrap(),
+        r#"{"foo": ["all", "cows", "eat", "grass"]}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            r#"[{"op": "test", "path": "/baz", "value": "qux"},
+                {"op": "test", "path": "/foo/1", "value": 2.0},
+                {"op": "copy", "from": "/foo", "path": "/bar"}]"#
+        )
+        .unwrap(),
+        r#"{"baz": "qux", "foo": ["a", 2, "c"], "bar": ["a", 2, "c"]}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#
+        )
+        .unwrap(),
+        r#"{"foo": "bar", "child": {"grandchild": {}}}"#
+    );
+    assert_eq!(
+        apply(
+            r#"{"foo": ["bar"]}"#,
+            r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#
+        )
+        .unwrap(),
+        r#"{"foo": ["bar", ["abc", "def"]]}"#
+    );
+
+    // A failing operation leaves the document untouched and reports its index
+    let mut doc = JSON::from_str(r#"{"a": 1, "b": [1, 2]}"#).unwrap();
+    let before = doc.clone();
+    let patch = patch_of(
+        r#"[{"op": "remove", "path": "/a"},
+            {"op": "add", "path": "/b/-", "value": 3},
+            {"op": "test", "path": "/b/0", "value": "1"}]"#,
+    );
+    let err = patch.apply(&mut doc).unwrap_err();
+    assert_eq!(err, PatchError::TestFailed(2, "/b/0".to_string()));
+    assert_eq!(err.index(), Some(2));
+    assert_eq!(doc, before);
+    assert_eq!(
+        apply(
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz/bat", "value": "qux"}]"#
+        ),
+        Err(PatchError::Pointer(
+            0,
+            PointerError::KeyNotFound(0, "baz".to_string())
+        ))
+    );
+    assert_eq!(
+        apply(
+            r#"{"a": {"b": {}}}"#,
+            r#"[{"op": "move", "from": "/a", "path": "/a/b/c"}]"#
+        ),
+        Err(PatchError::MoveIntoChild(0, "/a".to_string()))
+    );
+
+    let invalid = |s: &str| Patch::from_json(&JSON::from_str(s).unwrap()).unwrap_err();
+    assert_eq!(
+        invalid(r#"{"op": "add"}"#),
+        PatchError::InvalidPatch("Patch must be an array")
+    );
+    assert_eq!(
+        invalid(r#"[{"op": "remove", "path": "/a"}, {"op": "add", "path": "/a"}]"#),
+        PatchError::InvalidOperation(1, "Missing \"value\"")
+    );
+    assert_eq!(
+        invalid(r#"[{"op": "jump", "path": "/a"}]"#),
+        PatchError::InvalidOperation(0, "Unknown \"op\"")
+    );
+    assert_eq!(
+        invalid(r#"[{"op": "copy", "path": "/a"}]"#),
+        PatchError::InvalidOperation(0, "Missing or non-string \"from\"")
+    );
+
+    // Serializing and generating
+    let text = r#"[{"op":"move","from":"/a","path":"/b"},{"op":"test","path":"/b","value":null}]"#;
+    let patch = patch_of(text);
+    assert_eq!(
+        patch.0[0],
+        Operation::Move {
+            from: "/a".to_string(),
+            path: "/b".to_string()
+        }
+    );
+    assert_eq!(
+        patch
+            .to_json()
+            .to_string_pretty(&PrettyConfig::compact())
+            .unwrap(),
+        text
+    );
+
+    let from = JSON::from_str(r#"{"a": 1, "b": [1, 2, 3], "c/d": {"e": true}, "f": "x"}"#).unwrap();
+    let to = JSON::from_str(r#"{"a": 1.0, "b": [1, 5], "c/d": {"e": false, "g": null}, "h": []}"#)
+        .unwrap();
+    let patch = Patch::diff(&from, &to);
+    assert_eq!(
+        patch
+            .to_json()
+            .to_string_pretty(&PrettyConfig::compact())
+            .unwrap(),
+        concat!(
+            r#"[{"op":"replace","path":"/b/1","value":5},{"op":"remove","path":"/b/2"},"#,
+            r#"{"op":"replace","path":"/c~1d/e","value":false},{"op":"add","path":"/c~1d/g","value":null},"#,
+            r#"{"op":"remove","path":"/f"},{"op":"add","path":"/h","value":[]}]"#
+        )
+    );
+    let mut patched = from.clone();
+    patch.apply(&mut patched).unwrap();
+    assert_eq!(patched, to);
+    assert!(Patch::diff(&to, &to).0.is_empty());
+}
+
+#[test]
+fn test_merge_patch() {
+    // Test cases from RFC 7396 appendix A
+    let cases = [
+        (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+        (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+        (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+        (
+            r#"{"a":{"b":"c"}}"#,
+            r#"{"a":{"b":"d","c":null}}"#,
+            r#"{"a":{"b":"d"}}"#,
+        ),
+        (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+        (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+        (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+        (r#"{"a":"foo"}"#, "null", "null"),
+        (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+        (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+        (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+        (
+            r#"{}"#,
+            r#"{"a":{"bb":{"ccc":null}}}"#,
+            r#"{"a":{"bb":{}}}"#,
+        ),
+    ];
+    for (target, patch, result) in cases {
+        let mut doc = JSON::from_str(target).unwrap();
+        doc.merge_patch(&JSON::from_str(patch).unwrap());
+        assert_eq!(
+            doc,
+            JSON::from_str(result).unwrap(),
+            "{} + {}",
+            target,
+            patch
+        );
+    }
+
+    let from = JSON::from_str(
+        r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+    )
+    .unwrap();
+    let to = JSON::from_str(
+        r#"{"title": "Hello!", "author": {"givenName": "John"}, "tags": ["example"],
+            "content": "This will be unchanged", "phoneNumber": "+01-123-456-7890"}"#,
+    )
+    .unwrap();
+    let patch = JSON::merge_diff(&from, &to);
+    assert_eq!(
+        patch.to_string_pretty(&PrettyConfig::compact()).unwrap(),
+        r#"{"title":"Hello!","author":{"familyName":null},"tags":["example"],"phoneNumber":"+01-123-456-7890"}"#
+    );
+    let mut merged = from.clone();
+    merged.merge_patch(&patch);
+    assert_eq!(merged, to);
+    assert_eq!(JSON::merge_diff(&to, &to), JSON::Object(Map::new()));
+    let nested = JSON::from_str(r#"{"a": {"b": {"c": 1}}, "d": {"e": 2}}"#).unwrap();
+    let with_null = JSON::from_str(r#"{"a": {"b": {"c": 1, "x": null}}, "d": {"e": 3}}"#).unwrap();
+    assert_eq!(
+        JSON::merge_diff(&nested, &with_null),
+        JSON::from_str(r#"{"d": {"e": 3}}"#).unwrap()
+    );
+    assert_eq!(
+        JSON::merge_diff(
+            &JSON::from_str(r#"{"a": 1}"#).unwrap(),
+            &JSON::from_str(r#"{"a": {}}"#).unwrap()
+        ),
+        JSON::from_str(r#"{"a": {}}"#).unwrap()
+    );
+    assert_eq!(
+        JSON::merge_diff(
+            &JSON::from(1),
+            &JSON::from_str(r#"{"a":null,"b":{"c":null}}"#).unwrap()
+        ),
+        JSON::from_str(r#"{"b":{}}"#).unwrap()
+    );
+}
+
+#[test]
+fn test_diff() {
+    let old = JSON::from_str(
+        r#"{"name": "box", "size": [1.0, 2.5], "tags": ["a", "b", "c"], "owner": {"id": 7, "a/b": 1}}"#,
+    )
+    .unwrap();
+    let new = JSON::from_str(
+        r#"{"name": "crate", "size": [1, 2.5000001], "tags": ["c", "a"], "owner": {"id": 7}, "new": null}"#,
+    )
+    .unwrap();
+
+    let changes: Vec<String> = old.diff(&new).iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        changes,
+        [
+            r#"changed /name: "box" -> "crate""#,
+            "changed /size/1: 2.5 -> 2.5000001",
+            r#"changed /tags/0: "a" -> "c""#,
+            r#"changed /tags/1: "b" -> "a""#,
+            r#"removed /tags/2: "c""#,
+            "removed /owner/a~1b: 1",
+            "added /new: null",
+        ]
+    );
+    let config = DiffConfig {
+        arrays_as_sets: true,
+        float_tolerance: 1e-6,
+    };
+    let changes = old.diff_with(&new, &config);
+    assert_eq!(
+        changes[1],
+        Change::Removed {
+            path: "/tags/1".to_string(),
+            old: &JSON::from("b")
+        }
+    );
+    assert_eq!(
+        changes.iter().map(|c| c.path()).collect::<Vec<_>>(),
+        ["/name", "/tags/1", "/owner/a~1b", "/new"]
+    );
+    assert!(old.diff(&old.clone()).is_empty());
+    let sets = JSON::from_str("[[1, 2], [3]]").unwrap();
+    let shuffled = JSON::from_str("[[3], [2, 1]]").unwrap();
+    assert!(sets.diff_with(&shuffled, &config).is_empty());
+    assert_eq!(sets.diff(&shuffled).len(), 4);
+
+    assert_eq!(old.unified_diff(&old, true), "");
+    let a = JSON::from_str(r#"{"a": 1, "b": [1, 2, 3, 4, 5, 6, 7, 8, 9], "c": true}"#).unwrap();
+    let b =
+        JSON::from_str(r#"{"c": false, "b": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10], "a": 1}"#).unwrap();
+    assert_eq!(
+        a.unified_diff(&b, false),
+        concat!(
+            "--- old\n",
+            "+++ new\n",
+            "@@ -9,7 +9,8 @@\n",
+            "     6,\n",
+            "     7,\n",
+            "     8,\n",
+            "-    9\n",
+            "+    9,\n",
+            "+    10\n",
+            "   ],\n",
+            "-  \"c\": true\n",
+            "+  \"c\": false\n",
+            " }\n",
+        )
+    );
+    let colored = JSON::from(1).unified_diff(&JSON::from(2), true);
+    assert_eq!(
+        colored,
+        "\x1b[31m--- old\x1b[0m\n\x1b[32m+++ new\x1b[0m\n\x1b[36m@@ -1,1 +1,1 @@\x1b[0m\n\x1b[31m-1\x1b[0m\n\x1b[32m+2\x1b[0m\n"
+    );
+}
+
+#[test]
+fn test_canonical_json() {
+    // RFC 8785 section 3.2.2
+    let input = JSON::from_str(
+        r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        input.to_canonical_string().unwrap(),
+        r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+    );
+    let mut out = Vec::new();
+    input.write_canonical_to(&mut out).unwrap();
+    assert_eq!(out, input.to_canonical_string().unwrap().as_bytes());
+
+    // RFC 8785 section 3.2.3: keys sorted by UTF-16 code units, so U+1F600
+    // (surrogates D83D DE00) comes before U+FB33
+    let sorting = JSON::from_str(
+        r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "\ufb33": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        sorting.to_canonical_string().unwrap(),
+        concat!(
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+            "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+            "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        )
+    );
+
+    // RFC 8785 appendix B
+    let numbers = [
+        (0x0000000000000000, "0"),
+        (0x8000000000000000, "0"),
+        (0x0000000000000001, "5e-324"),
+        (0x8000000000000001, "-5e-324"),
+        (0x7fefffffffffffff, "1.7976931348623157e+308"),
+        (0xffefffffffffffff, "-1.7976931348623157e+308"),
+        (0x4340000000000000, "9007199254740992"),
+        (0xc340000000000000, "-9007199254740992"),
+        (0x4430000000000000, "295147905179352830000"),
+        (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+        (0x44b52d02c7e14af6, "1e+23"),
+        (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+        (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+        (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+        (0x444b1ae4d6e2ef50, "1e+21"),
+        (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+        (0x3eb0c6f7a0b5ed8d, "0.000001"),
+        (0x41b3de4355555553, "333333333.3333332"),
+        (0x41b3de4355555554, "333333333.33333325"),
+        (0x41b3de4355555555, "333333333.3333333"),
+        (0x41b3de4355555556, "333333333.3333334"),
+        (0x41b3de4355555557, "333333333.33333343"),
+        (0xbecbf647612f3696, "-0.0000033333333333333333"),
+        (0x43143ff3c1cb0959, "1424953923781206.2"),
+    ];
+    for (bits, expected) in numbers {
+        let value = JSON::from(f64::from_bits(bits));
+        assert_eq!(value.to_canonical_string().unwrap(), expected, "{:x}", bits);
+    }
+    for bits in [0x7fffffffffffffff, 0x7ff0000000000000] {
+        let value = JSON::Array(vec![JSON::from(f64::from_bits(bits))]);
+        assert!(matches!(
+            value.to_canonical_string(),
+            Err(JSONWriterError::NonFiniteNumber)
+        ));
+    }
+    assert_eq!(
+        JSON::from_str("[18446744073709551615, -0.0, 100]")
+            .unwrap()
+            .to_canonical_string()
+            .unwrap(),
+        "[18446744073709552000,0,100]"
+    );
+}
+
+#[test]
+fn test_float_formatting() {
+    for (f, expected) in [
+        (0.1, "0.1"),
+        (1.5, "1.5"),
+        (-2.0, "-2"),
+        (-0.0, "-0"),
+        (100.0, "100"),
+        (1e21, "1e+21"),
+        (1.5e300, "1.5e+300"),
+        (123456789012345680000.0, "123456789012345680000.0"),
+        (-5.34574943530375e16, "-53457494353037500.0"),
+        (9007199254740991.0, "9007199254740991"),
+        (0.000001, "0.000001"),
+        (1e-7, "1e-7"),
+        (-2.5e-10, "-2.5e-10"),
+        (f64::MAX, "1.7976931348623157e+308"),
+        (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+        (5e-324, "5e-324"),
+    ] {
+        assert_eq!(JSON::from(f).to_string(), expected);
+    }
+
+    // Every formatted value parses back to exactly the same f64 and an equal value
+    let mut bits: u64 = 0x9e3779b97f4a7c15;
+    for _ in 0..10_000 {
+        bits ^= bits << 13;
+        bits ^= bits >> 7;
+        bits ^= bits << 17;
+        let f = f64::from_bits(bits);
+        if !f.is_finite() {
+            continue;
+        }
+        let text = JSON::from(f).to_string();
+        let parsed = JSON::from_str(&text).unwrap();
+        assert_eq!(
+            parsed.as_f64().unwrap().to_bits(),
+            f.to_bits(),
+            "{:e} written as {}",
+            f,
+            text
+        );
+        assert_eq!(parsed, JSON::from(f), "{:e} written as {}", f, text);
+    }
+
+    let values = JSON::Array(vec![
+        JSON::from(f64::NAN),
+        JSON::from(f64::INFINITY),
+        JSON::from(f64::NEG_INFINITY),
+        JSON::from(1.0),
+    ]);
+    assert_eq!(values.to_string(), "[null, null, null, 1]");
+    let strings = PrettyConfig {
+        non_finite: NonFinite::String,
+        ..PrettyConfig::compact()
+    };
+    assert_eq!(
+        values.to_string_pretty(&strings).unwrap(),
+        r#"["NaN","Infinity","-Infinity",1]"#
+    );
+
+    let errors = PrettyConfig {
+        non_finite: NonFinite::Error,
+        ..PrettyConfig::compact()
+    };
+    assert!(matches!(
+        values.to_string_pretty(&errors),
+        Err(JSONWriterError::NonFiniteNumber)
+    ));
+    assert_eq!(JSON::from(1e22).to_string_pretty(&errors).unwrap(), "1e+22");
+    // Kept text is written as it is, but can't be converted for canonical JSON
+    #[cfg(feature = "arbitrary_precision")]
+    {
+        let huge = JSON::from_str("[1e400]").unwrap();
+        assert_eq!(huge.to_string_pretty(&errors).unwrap(), "[1e400]");
+        assert!(matches!(
+            huge.to_canonical_string(),
+            Err(JSONWriterError::NonFiniteNumber)
+        ));
+    }
+    let mut out = Vec::new();
+    let err = values.write_pretty_to(&mut out, &errors).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(out.is_empty());
+
+    let mut writer = JSONWriter::with_config(Vec::new(), errors);
+    writer.begin_array().unwrap();
+    assert!(matches!(
+        writer.number(f64::NAN),
+        Err(JSONWriterError::NonFiniteNumber)
+    ));
+    writer.number(0.5).unwrap();
+    writer.end_array().unwrap();
+    assert_eq!(writer.finish().unwrap(), b"[0.5]");
+}
+
+#[test]
+fn test_json5() {
+    let text = "// Settings
+{
+  name: 'json5', \"quoted\": \"it's\", $id_2: 'say \"hi\"',
+  /* numbers */
+  numbers: [0x1F, -0X10, .5, 5., +1, 1e3, Infinity, -Infinity, NaN,],
+  text: 'line \\
+continued \\x41\\v\\0',
+}
+";
+    let value = JSON::from_json5(text).unwrap();
+    assert_eq!(value.get("name").unwrap(), &JSON::from("json5"));
+    assert_eq!(value.get("quoted").unwrap(), &JSON::from("it's"));
+    assert_eq!(value.get("$id_2").unwrap(), &JSON::from("say \"hi\""));
+    let numbers = value.get("numbers").unwrap().as_array().unwrap();
+    assert_eq!(numbers.len(), 9);
+    assert_eq!(numbers[0].as_u64(), Some(31));
+    assert_eq!(numbers[1].as_i64(), Some(-16));
+    assert_eq!(numbers[2].as_f64(), Some(0.5));
+    assert_eq!(numbers[3].as_f64(), Some(5.0));
+    assert_eq!(numbers[4].as_u64(), Some(1));
+    assert_eq!(numbers[5].as_f64(), Some(1000.0));
+    assert_eq!(numbers[6].as_f64(), Some(f64::INFINITY));
+    assert_eq!(numbers[7].as_f64(), Some(f64::NEG_INFINITY));
+    assert!(numbers[8].as_f64().unwrap().is_nan());
+    assert_eq!(
+        value.get("text").unwrap(),
+        &JSON::from("line continued A\x0b\0")
+    );
+
+    // None of the extensions are accepted by the JSON parser
+    for json in [
+        "{a: 1}", "['a']", "[0x1]", "[.5]", "[+1]", "[NaN]", "[1,]", "[1] // c",
+    ] {
+        assert!(JSON::from_str(json).is_err(), "{}", json);
+    }
+
+    match JSON::from_json5("{a: 1, b-c: 2}") {
+        Err(JSONError::UnexpectedCharacter(
+            '-',
+            Position {
+                line: 1, column: 9, ..
+            },
+        )) => (),
+        other => panic!("{:?}", other),
+    }
+    match JSON::from_json5("[007]") {
+        Err(JSONError::UnexpectedCharacter(
+            '0',
+            Position {
+                line: 1, column: 3, ..
+            },
+        )) => (),
+        other => panic!("{:?}", other),
+    }
+    match JSON::from_json5("['a\\\nb\\1']") {
+        Err(JSONError::InvalidEscape(
+            '1',
+            Position {
+                line: 2, column: 2, ..
+            },
+        )) => (),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(
+        JSON::from_json5(r#"['\uD83D\uDE00', "\u00e9"]"#).unwrap(),
+        JSON::from_str(r#"["\ud83d\ude00", "\u00e9"]"#).unwrap()
+    );
+    match JSON::from_json5(r"[1, '\uDE00']") {
+        Err(JSONError::LoneSurrogate(
+            0xDE00,
+            Position {
+                line: 1, column: 6, ..
+            },
+        )) => (),
+        other => panic!("{:?}", other),
+    }
+    match JSON::from_json5("[1, /* open") {
+        Err(JSONError::UnexpectedEndOfInput(_)) => (),
+        other => panic!("{:?}", other),
+    }
+
+    // `arbitrary_precision` keeps the text of numbers that aren't integers
+    let thousand = if cfg!(feature = "arbitrary_precision") {
+        "1e3"
+    } else {
+        "1000"
+    };
+    assert_eq!(
+        value.to_json5_string(),
+        format!(
+            "{{
+  name: \"json5\",
+  quoted: \"it's\",
+  $id_2: 'say \"hi\"',
+  numbers: [
+    31,
+    -16,
+    0.5,
+    5,
+    1,
+    {},
+    Infinity,
+    -Infinity,
+    NaN
+  ],
+  text: \"line continued A\\u000b\\u0000\"
+}}",
+            thousand
+        )
+    );
+    let mut map = Map::new();
+    map.insert("not an identifier".to_string(), JSON::from(1));
+    assert_eq!(
+        JSON::Object(map).to_json5_string(),
+        "{\n  \"not an identifier\": 1\n}"
+    );
+    let reparsed = JSON::from_json5(&value.to_json5_string()).unwrap();
+    assert_eq!(reparsed.get("text").unwrap(), value.get("text").unwrap());
+    assert_eq!(reparsed.get("$id_2").unwrap(), value.get("$id_2").unwrap());
+}
+
+#[test]
+fn test_jsonc() {
+    let text = "{
+  // Editor settings
+  \"editor.tabSize\": 4, /* spaces,
+  not tabs */ \"files.exclude\": [\"a/*b\", \"//c\",],
+}
+";
+    let (value, comments) = JSON::from_jsonc_with_comments(text).unwrap();
+    assert_eq!(value.get("editor.tabSize").unwrap().as_u64(), Some(4));
+    assert_eq!(
+        value.get("files.exclude").unwrap(),
+        &JSON::Array(vec![JSON::from("a/*b"), JSON::from("//c")])
+    );
+    assert_eq!(
+        comments,
+        vec![
+            Comment {
+                text: "// Editor settings",
+                position: Position {
+                    line: 2,
+                    column: 3,
+                    offset: 4
+                }
+            },
+            Comment {
+                text: "/* spaces,\n  not tabs */",
+                position: Position {
+                    line: 3,
+                    column: 24,
+                    offset: 46
+                }
+            },
+        ]
+    );
+    assert_eq!(comments[0].content(), " Editor settings");
+    assert!(comments[1].is_block());
+    assert_eq!(JSON::from_jsonc(text).unwrap(), value);
+
+    // Positions after a multi-line comment are still right
+    let mut reader = EventReader::jsonc("/* a\nb */ [1, // c\r\n  x]").with_comments();
+    assert!(matches!(reader.next(), Some(Ok((Event::StartArray, _)))));
+    assert_eq!(reader.take_comments().len(), 1);
+    assert!(matches!(reader.next(), Some(Ok((Event::Number(_), _)))));
+    match reader.next() {
+        Some(Err(JSONError::UnexpectedCharacter(
+            'x',
+            Position {
+                line: 3, column: 3, ..
+            },
+        ))) => (),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(reader.take_comments()[0].text, "// c");
+
+    // Plain JSON still rejects comments, JSONC still rejects JSON5 extensions
+    assert!(JSON::from_str("[1] // c").is_err());
+    assert!(JSON::from_str("[1, /* c */ 2]").is_err());
+    assert!(JSON::from_jsonc("{a: 1}").is_err());
+    assert!(JSON::from_jsonc("['a']").is_err());
+    assert!(JSON::from_jsonc("[1 / 2]").is_err());
+    assert!(matches!(
+        JSON::from_jsonc("[1] /* open"),
+        Err(JSONError::UnexpectedEndOfInput(_))
+    ));
+}
+
+#[test]
+fn test_error_positions() {
+    let err = JSON::from_str(r#"{"a": 1,,}"#).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedComma);
+    assert_eq!(
+        err.position(),
+        Position {
+            line: 1,
+            column: 9,
+            offset: 8
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Parse error: Unexpected comma at line 1 position 9"
+    );
+    assert_eq!(
+        err.render(r#"{"a": 1,,}"#),
+        "error: Unexpected comma\n --> 1:9\n  |\n1 | {\"a\": 1,,}\n  |         ^\n"
+    );
+
+    // Tabs are kept in the caret line, so it lines up however they are shown
+    let source = "[\n\t1,\n\t}";
+    let err = JSON::from_str(source).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEndOfObject);
+    assert_eq!(err.position().offset, 7);
+    assert_eq!(
+        err.render(source),
+        "error: Unexpected end curly brace\n --> 3:2\n  |\n3 | \t}\n  | \t^\n"
+    );
+
+    let source = "[1, 2";
+    let err = JSON::from_str(source).unwrap_err();
+    assert!(matches!(
+        err,
+        JSONError::UnexpectedEndOfInput(Position {
+            line: 1,
+            column: 6,
+            offset: 5
+        })
+    ));
+    assert!(err.render(source).ends_with("1 | [1, 2\n  |      ^\n"));
+
+    // Every way of parsing reports the same positions
+    let source = "{\n  \"a\": [1, 2],\n  \"b\": tru\n}";
+    let expected = Position {
+        line: 3,
+        column: 8,
+        offset: 24,
+    };
+    for err in [
+        JSON::from_str(source).unwrap_err(),
+        JSON::from_reader(source.as_bytes()).unwrap_err(),
+        EventReader::new(source).find_map(Result::err).unwrap(),
+        StreamDeserializer::new(source).next().unwrap().unwrap_err(),
+    ] {
+        assert_eq!(err.kind(), ErrorKind::UnexpectedCharacter);
+        assert_eq!(err.position(), expected);
+    }
+    let err = NDJSONIter::new("1\n\n[x]\n").nth(1).unwrap().unwrap_err();
+    assert_eq!(
+        err.position(),
+        Position {
+            line: 3,
+            column: 2,
+            offset: 4
+        }
+    );
+    let err = JSON::from_reader(&b"[\"\xff\"]"[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidUtf8);
+    assert_eq!(err.position().column, 3);
+
+    let err: Box<dyn std::error::Error> = Box::new(JSON::from_str("[").unwrap_err());
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn test_recovering_parse() {
+    let source = r#"{
+  "a": 1
+  "b": [1 2, tru, 3],
+  "c" 4,
+  "d": : 5,
+  "e": {"x": [1},
+  "f": null
+"#;
+    let (value, errors) = JSON::from_str_recovering(source);
+    let found: Vec<(ErrorKind, usize, usize)> = errors
+        .iter()
+        .map(|e| (e.kind(), e.position().line, e.position().column))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorKind::UnexpectedString, 3, 3),
+            (ErrorKind::UnexpectedNumber, 3, 11),
+            (ErrorKind::UnexpectedCharacter, 3, 14),
+            (ErrorKind::UnexpectedNumber, 4, 7),
+            (ErrorKind::UnexpectedColon, 5, 8),
+            (ErrorKind::UnexpectedEndOfObject, 6, 16),
+            (ErrorKind::UnexpectedEndOfInput, 8, 1),
+        ]
+    );
+    assert_eq!(
+        value.unwrap(),
+        JSON::from_str(r#"{"a": 1, "b": [1, 2, 3], "c": 4, "d": 5, "e": {"x": [1]}, "f": null}"#)
+            .unwrap()
+    );
+
+    // Valid input gives the same value as `from_str` and no errors
+    let valid = r#"{"a": [1, {"b": null}], "c": "d"}"#;
+    let (value, errors) = JSON::from_str_recovering(valid);
+    assert!(errors.is_empty());
+    assert_eq!(value.unwrap(), JSON::from_str(valid).unwrap());
+
+    for (bad, expected, kinds) in [
+        ("[1]]", "[1]", vec![ErrorKind::UnexpectedEndOfArray]),
+        ("[1, 2,]", "[1, 2]", vec![ErrorKind::TrailingCommaInArray]),
+        (r#"{"a": }"#, "{}", vec![ErrorKind::UnexpectedEndOfObject]),
+        (
+            r#"{1: 2, "b": 3}"#,
+            r#"{"b": 3}"#,
+            vec![ErrorKind::NonStringKey],
+        ),
+        (
+            r#"{"a":1, : }"#,
+            r#"{"a": 1}"#,
+            vec![ErrorKind::UnexpectedColon],
+        ),
+        (
+            r#"{"a":1, : , "b": [,]}"#,
+            r#"{"a": 1, "b": []}"#,
+            vec![
+                ErrorKind::UnexpectedColon,
+                ErrorKind::UnexpectedComma,
+                ErrorKind::TrailingCommaInArray,
+            ],
+        ),
+        ("[1, \\, 2]", "[1, 2]", vec![ErrorKind::UnexpectedCharacter]),
+        ("1 2", "1", vec![ErrorKind::MultipleValues]),
+    ] {
+        let (value, errors) = JSON::from_str_recovering(bad);
+        assert_eq!(value.unwrap(), JSON::from_str(expected).unwrap(), "{}", bad);
+        let found: Vec<ErrorKind> = errors.iter().map(JSONError::kind).collect();
+        assert_eq!(found, kinds, "{}", bad);
+    }
+
+    let (value, errors) = JSON::from_str_recovering("");
+    assert!(value.is_none());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_parse_limits() {
+    let source = r#"{"a": [1, 2, 3], "b": {"c": "hello"}}"#;
+    // Only the depth is limited by default, to `DEFAULT_MAX_DEPTH`, and exactly at
+    // each limit still parses
+    let at_limits = ParseOptions {
+        max_depth: Some(2),
+        max_string_bytes: Some(5),
+        max_number_bytes: Some(1),
+        max_container_elements: Some(3),
+        max_values: Some(7),
+        max_input_bytes: Some(source.len()),
+        ..ParseOptions::default()
+    };
+    let expected = JSON::from_str(source).unwrap();
+    assert_eq!(
+        JSON::from_str_with(source, &ParseOptions::new()).unwrap(),
+        expected
+    );
+    assert_eq!(JSON::from_str_with(source, &at_limits).unwrap(), expected);
+    assert_eq!(
+        JSON::from_reader_with(source.as_bytes(), &at_limits).unwrap(),
+        expected
+    );
+
+    let tighter = |f: fn(&mut ParseOptions)| {
+        let mut options = at_limits.clone();
+        f(&mut options);
+        options
+    };
+    for (options, kind, column) in [
+        (
+            tighter(|o| o.max_depth = Some(1)),
+            ErrorKind::DepthLimitExceeded,
+            7,
+        ),
+        (
+            tighter(|o| o.max_string_bytes = Some(4)),
+            ErrorKind::StringTooLong,
+            29,
+        ),
+        (
+            tighter(|o| o.max_number_bytes = Some(0)),
+            ErrorKind::NumberTooLong,
+            8,
+        ),
+        (
+            tighter(|o| o.max_container_elements = Some(2)),
+            ErrorKind::TooManyElements,
+            14,
+        ),
+        (
+            tighter(|o| o.max_values = Some(6)),
+            ErrorKind::TooManyValues,
+            29,
+        ),
+        (
+            tighter(|o| o.max_input_bytes = Some(10)),
+            ErrorKind::InputTooLarge,
+            11,
+        ),
+    ] {
+        let err = JSON::from_str_with(source, &options).unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position().column),
+            (kind, column),
+            "{}",
+            err
+        );
+        let err = JSON::from_reader_with(source.as_bytes(), &options).unwrap_err();
+        assert_eq!(err.kind(), kind, "{}", err);
+    }
+    assert_eq!(
+        JSON::from_str_with(source, &tighter(|o| o.max_string_bytes = Some(4)))
+            .unwrap_err()
+            .to_string(),
+        "String longer than the limit of 4 bytes at line 1 position 29"
+    );
+
+    // Deep nesting is rejected before anything is built
+    let deep = "[".repeat(1_000_000);
+    let options = ParseOptions {
+        max_depth: Some(64),
+        ..ParseOptions::default()
+    };
+    match JSON::from_str_with(&deep, &options) {
+        Err(JSONError::DepthLimitExceeded(64, p)) => assert_eq!(p.offset, 64),
+        other => panic!("{:?}", other),
+    }
+
+    // A reader stops as soon as a string or number can only be too long, even
+    // if it never ends
+    let options = ParseOptions {
+        max_string_bytes: Some(16),
+        max_number_bytes: Some(32),
+        ..ParseOptions::default()
+    };
+    let endless_string = std::io::Read::chain(r#"{"a": ""#.as_bytes(), std::io::repeat(b'a'));
+    match JSON::from_reader_with(endless_string, &options) {
+        Err(JSONError::StringTooLong(16, p)) => assert_eq!(p.column, 7),
+        other => panic!("{:?}", other),
+    }
+    let endless_number = std::io::Read::chain("[1".as_bytes(), std::io::repeat(b'1'));
+    match JSON::from_reader_with(endless_number, &options) {
+        Err(JSONError::NumberTooLong(32, p)) => assert_eq!(p.column, 2),
+        other => panic!("{:?}", other),
+    }
+    let escaped = format!(r#"["{}"]"#, r"\u0041".repeat(16));
+    assert!(JSON::from_reader_with(escaped.as_bytes(), &options).is_ok());
+
+    // Parsing caps depth by default, since dropping a value recurses
+    let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+    let value = JSON::from_str(&nested(DEFAULT_MAX_DEPTH)).unwrap();
+    assert_eq!(value.clone(), value);
+    drop(value);
+    let too_deep = nested(200_000);
+    match JSON::from_str(&too_deep) {
+        Err(JSONError::DepthLimitExceeded(DEFAULT_MAX_DEPTH, p)) => {
+            assert_eq!(p.offset, DEFAULT_MAX_DEPTH)
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(
+        JSON::from_reader(too_deep.as_bytes()),
+        Err(JSONError::DepthLimitExceeded(..))
+    ));
+    let (value, errors) = JSON::from_str_recovering(&too_deep);
+    assert_eq!(
+        errors.iter().map(JSONError::kind).collect::<Vec<_>>(),
+        vec![ErrorKind::DepthLimitExceeded]
+    );
+    drop(value);
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let source = r#"{"a": 1, "b": {"a": 2, "a": 3}, "a": [4], "a": 5}"#;
+    let parse = |duplicate_keys| {
+        let options = ParseOptions {
+            duplicate_keys,
+            ..ParseOptions::default()
+        };
+        JSON::from_str_with(source, &options)
+    };
+
+    // The default: the last value wins, at the position of the first key
+    assert_eq!(
+        ParseOptions::default().duplicate_keys,
+        DuplicateKeys::LastWins
+    );
+    let value = JSON::from_str(source).unwrap();
+    assert_eq!(value.to_string(), r#"{"a": 5, "b": {"a": 3}}"#);
+    assert_eq!(parse(DuplicateKeys::LastWins).unwrap(), value);
+    assert_eq!(JSON::from_reader(source.as_bytes()).unwrap(), value);
+
+    assert_eq!(
+        parse(DuplicateKeys::FirstWins).unwrap().to_string(),
+        r#"{"a": 1, "b": {"a": 2}}"#
+    );
+    // Only repeated keys become arrays, and an array value is kept as one
+    assert_eq!(
+        parse(DuplicateKeys::Collect).unwrap().to_string(),
+        r#"{"a": [1, [4], 5], "b": {"a": [2, 3]}}"#
+    );
+    assert_eq!(
+        JSON::from_str_with(
+            r#"{"a": [1], "b": 2}"#,
+            &ParseOptions {
+                duplicate_keys: DuplicateKeys::Collect,
+                ..ParseOptions::default()
+            }
+        )
+        .unwrap()
+        .to_string(),
+        r#"{"a": [1], "b": 2}"#
+    );
+
+    // The first repeat is reported, with where the key first appeared
+    let err = parse(DuplicateKeys::Error).unwrap_err();
+    match &err {
+        JSONError::DuplicateKey(key, first, again) => {
+            assert_eq!(key, "a");
+            assert_eq!((first.line, first.column), (1, 16));
+            assert_eq!((again.line, again.column), (1, 24));
+        }
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(err.kind(), ErrorKind::DuplicateKey);
+    assert_eq!(
+        err.to_string(),
+        r#"Duplicate key "a" (first at line 1 position 16) at line 1 position 24"#
+    );
+    let options = ParseOptions {
+        duplicate_keys: DuplicateKeys::Error,
+        ..ParseOptions::default()
+    };
+    let err = JSON::from_reader_with(source.as_bytes(), &options).unwrap_err();
+    assert_eq!(err.position().column, 24);
+    // The same key in different objects is fine
+    assert!(JSON::from_str_with(r#"[{"a": 1}, {"a": {"a": 2}}]"#, &options).is_ok());
+}